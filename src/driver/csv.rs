@@ -0,0 +1,251 @@
+//! A read-only driver for a directory of [CSV][1] files. Every type maps to a
+//! file named `<type>.csv` whose first row names the columns. Each row is read
+//! as an object with a property for every column.
+//!
+//! Cells are coerced into values with the schemas of the type’s properties.
+//! Numbers become `I64` if they are integral and `F64` otherwise, integers
+//! must be integral, booleans are `true` or `false`, and every other cell is a
+//! string. Empty cells are always `null`.
+//!
+//! Conditions, sort rules, ranges, and queries are evaluated in memory with
+//! `read_values`.
+//!
+//! [1]: https://tools.ietf.org/html/rfc4180
+
+use std::error::Error as ErrorTrait;
+use std::path::PathBuf;
+
+use csv::{Reader, Error as CsvError};
+use linear_map::LinearMap;
+use url::Url;
+
+use driver::{Driver, Connect};
+use driver::memory::read_values;
+use error::{Error, MethodNotAllowed};
+use query::{Range, SortRule, Condition, Query};
+use schema::{Type, Schema, SchemaNumber, SchemaBoolean};
+use value::{Value, ValueIter};
+
+/// A driver reading the values of every type from a CSV file inside a single
+/// directory.
+pub struct CsvDriver {
+  directory: PathBuf
+}
+
+impl CsvDriver {
+  /// Creates a new driver for a directory of CSV files.
+  pub fn new<P>(directory: P) -> Self where P: Into<PathBuf> {
+    CsvDriver {
+      directory: directory.into()
+    }
+  }
+
+  /// Loads every row of a type as an object. A type without a file has no
+  /// values.
+  fn load(&self, type_: &Type) -> Result<Vec<Value>, Error> {
+    let path = self.directory.join(format!("{}.csv", type_.name()));
+
+    if !path.is_file() {
+      return Ok(Vec::new());
+    }
+
+    let properties = type_.properties();
+    let mut reader = try!(Reader::from_file(&path)).has_headers(true);
+    let headers = try!(reader.headers());
+    let mut values = Vec::new();
+
+    for (i, record) in reader.records().enumerate() {
+      let record = try!(record);
+      let mut object = LinearMap::new();
+      for (j, (header, cell)) in headers.iter().zip(record.into_iter()).enumerate() {
+        let value = try!(coerce_cell(properties.get(header).cloned(), cell).map_err(|expected| Error::invalid(
+          format!("Cannot coerce cell in row {} column {} of '{}' to {}.", i + 2, j + 1, path.display(), expected),
+          format!("Try using {} or an empty cell for the \"{}\" column.", expected, header)
+        )));
+        object.insert(header.to_owned(), value);
+      }
+      values.push(Value::Object(object));
+    }
+
+    Ok(values)
+  }
+}
+
+impl Connect for CsvDriver {
+  /// Connects to a directory with a URL like `csv:path/to/data` or
+  /// `csv:///path/to/data` for an absolute path.
+  fn connect(url: &Url) -> Result<Self, Error> {
+    match (url.scheme.as_str(), url.non_relative_scheme_data()) {
+      ("csv", Some(path)) if !path.is_empty() => Ok(CsvDriver::new(path.trim_left_matches("//"))),
+      _ => Err(Error::invalid(
+        format!("Cannot connect to '{}' with the CSV driver.", url),
+        "Try a URL like `csv:path/to/data`."
+      ))
+    }
+  }
+}
+
+impl Driver for CsvDriver {
+  fn read(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    range: Range,
+    query: Query
+  ) -> Result<ValueIter, Error> {
+    let values = try!(self.load(type_));
+    Ok(ValueIter::new(read_values(values.iter(), condition, sort, range, query).into_iter()))
+  }
+
+  fn create(
+    &self,
+    type_: &Type,
+    _: Vec<Value>
+  ) -> Result<Vec<Value>, Error> {
+    Err(read_only(type_))
+  }
+
+  fn update(
+    &self,
+    type_: &Type,
+    _: Condition,
+    _: Value
+  ) -> Result<u64, Error> {
+    Err(read_only(type_))
+  }
+
+  fn delete(
+    &self,
+    type_: &Type,
+    _: Condition
+  ) -> Result<u64, Error> {
+    Err(read_only(type_))
+  }
+}
+
+impl From<CsvError> for Error {
+  fn from(error: CsvError) -> Self {
+    Error::internal(error.description())
+  }
+}
+
+/// Creates the error for when a write is attempted on a CSV type.
+fn read_only(type_: &Type) -> Error {
+  Error::new(MethodNotAllowed, format!("Cannot write to type '{}' because CSV files are read-only.", type_.name()))
+    .set_hint("Try using a driver which supports writing, like the file driver.")
+}
+
+/// Coerces a cell into a value using the schema of its column. Returns the
+/// name of the expected type, with an article, if the cell could not be
+/// coerced.
+fn coerce_cell(schema: Option<&Schema>, cell: String) -> Result<Value, &'static str> {
+  if cell.is_empty() {
+    return Ok(Value::Null);
+  }
+
+  match schema {
+    Some(schema) if schema.downcast_ref::<SchemaNumber>().is_some() => {
+      let integer = schema.downcast_ref::<SchemaNumber>().map_or(false, SchemaNumber::integer);
+      if let Ok(number) = cell.trim().parse::<i64>() {
+        Ok(Value::I64(number))
+      } else if let Ok(number) = cell.trim().parse::<f64>() {
+        if !integer {
+          Ok(Value::F64(number))
+        } else if number.fract() == 0.0 {
+          Ok(Value::I64(number as i64))
+        } else {
+          Err("an integer")
+        }
+      } else {
+        Err(if integer { "an integer" } else { "a number" })
+      }
+    },
+    Some(schema) if schema.downcast_ref::<SchemaBoolean>().is_some() => {
+      match cell.trim() {
+        "true" => Ok(Value::Boolean(true)),
+        "false" => Ok(Value::Boolean(false)),
+        _ => Err("a boolean")
+      }
+    },
+    _ => Ok(Value::String(cell))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs::File;
+  use std::io::Write;
+
+  use url::Url;
+
+  use driver::{Driver, Connect};
+  use driver::csv::CsvDriver;
+  use driver::testing::{read, temp_directory};
+  use error::MethodNotAllowed;
+  use query::{Range, SortRule, Condition, Query};
+  use schema::{Type, Schema};
+  use value::Value;
+
+  fn get_fixtures(name: &str, contents: &str) -> (Type, CsvDriver) {
+    let directory = temp_directory("csv", name);
+    File::create(directory.join("person.csv")).unwrap().write_all(contents.as_bytes()).unwrap();
+    let mut type_ = Type::new();
+    type_.set_name("person");
+    type_.add_property("name", Schema::string());
+    type_.add_property("age", Schema::number());
+    type_.add_property("alive", Schema::boolean());
+    type_.add_property("children", Schema::integer());
+    (type_, CsvDriver::new(directory))
+  }
+
+  #[test]
+  fn test_connect() {
+    assert!(CsvDriver::connect(&Url::parse("csv:path/to/data").unwrap()).is_ok());
+    CsvDriver::connect(&Url::parse("sqlite::memory:").unwrap()).err().unwrap().expect("Cannot connect");
+  }
+
+  #[test]
+  fn test_read() {
+    let (type_, driver) = get_fixtures("read", "name,age,alive,city\nAda,36,false,London\nAlan,41.5,true,\n\"Grace, Rear Admiral\",,true,New York\n");
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Default::default(), Default::default()), vec![
+      value!({ "name" => "Ada", "age" => 36, "alive" => false, "city" => "London" }),
+      value!({ "name" => "Alan", "age" => 41.5, "alive" => true, "city" => () }),
+      value!({ "name" => "Grace, Rear Admiral", "age" => (), "alive" => true, "city" => "New York" })
+    ]);
+    assert_eq!(
+      read(&driver, &type_, Condition::Keys(linear_map! { str!("alive") => Condition::Equal(Value::Boolean(true)) }), vec![SortRule::new(point!["name"], false)], Range::new(None, Some(1)), Query::Keys(linear_map! {
+        str!("name") => Query::All
+      })),
+      vec![value!({ "name" => "Grace, Rear Admiral" })]
+    );
+    let mut other_type = Type::new();
+    other_type.set_name("other");
+    assert_eq!(read(&driver, &other_type, Default::default(), vec![], Default::default(), Default::default()), vec![]);
+  }
+
+  #[test]
+  fn test_read_coercion_failure() {
+    let (type_, driver) = get_fixtures("coercion-failure", "name,age,alive\nAda,36,false\nAlan,41,yes\n");
+    driver.read(&type_, Default::default(), vec![], Default::default(), Default::default()).err().unwrap().expect("row 3 column 3 .* to a boolean");
+    let (type_, driver) = get_fixtures("coercion-integer", "name,children\nAda,3.0\nAlan,1.5\n");
+    driver.read(&type_, Default::default(), vec![], Default::default(), Default::default()).err().unwrap().expect("row 3 column 2 .* to an integer");
+  }
+
+  #[test]
+  fn test_read_integer() {
+    let (type_, driver) = get_fixtures("integer", "name,children\nAda,3\nAlan,2.0\n");
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Default::default(), Default::default()), vec![
+      value!({ "name" => "Ada", "children" => 3 }),
+      value!({ "name" => "Alan", "children" => 2 })
+    ]);
+  }
+
+  #[test]
+  fn test_read_only() {
+    let (type_, driver) = get_fixtures("read-only", "name\nAda\n");
+    assert_eq!(driver.create(&type_, vec![value!({ "name" => "Alan" })]).unwrap_err().code(), &MethodNotAllowed);
+    assert_eq!(driver.update(&type_, Condition::True, value!({})).unwrap_err().code(), &MethodNotAllowed);
+    assert_eq!(driver.delete(&type_, Condition::True).unwrap_err().code(), &MethodNotAllowed);
+  }
+}
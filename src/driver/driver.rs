@@ -1,20 +1,28 @@
 use url::Url;
 
 use error::{Error, NotFound};
-use query::{Condition, SortRule, Range, Query};
+use query::{Condition, SortRule, Range, Query, Cursor, Aggregation};
 use schema::Type;
 use value::{Value, ValueIter};
 
-/// The driver trait which all drivers will implement. Designed to be
-/// interoperable with any data source, however the driver also assumes a
-/// collection based data model.
-pub trait Driver {
+/// Constructs a driver from a URL. Separate from `Driver` because a
+/// constructor returning `Self` would stop `Driver` from being object safe.
+pub trait Connect: Driver + Sized {
   /// Connects to a driver and returns a driver instance. After calling this
   /// the driver is ready to roll!
   ///
   /// No schema definition is provided to the driver in its construction step.
-  fn connect(url: &Url) -> Result<Self, Error> where Self: Sized;
+  fn connect(url: &Url) -> Result<Self, Error>;
+}
 
+/// The driver trait which all drivers will implement. Designed to be
+/// interoperable with any data source, however the driver also assumes a
+/// collection based data model.
+///
+/// The trait is object safe so that drivers for different data sources may be
+/// stored together, like in a `Vec<Box<Driver>>`. Constructing a driver is
+/// done with the separate `Connect` trait.
+pub trait Driver {
   /// Lazily read some values from the driver.
   ///
   /// Designed against a couple of database specifications. Including the
@@ -34,6 +42,129 @@ pub trait Driver {
     query: Query
   ) -> Result<ValueIter, Error>;
 
+  /// Create some new values in the driver. Returns the values as they were
+  /// written.
+  ///
+  /// Designed against a couple of database specifications. Including the
+  /// following:
+  ///
+  /// - [SQL `INSERT` statement][1].
+  /// - [MongoDB `insert` command][2].
+  ///
+  /// [1]: http://www.postgresql.org/docs/current/static/sql-insert.html
+  /// [2]: https://docs.mongodb.org/manual/reference/command/insert/
+  fn create(
+    &self,
+    type_: &Type,
+    values: Vec<Value>
+  ) -> Result<Vec<Value>, Error>;
+
+  /// Update all of the values which pass the condition with a patch. The
+  /// patch is an object whose properties replace the properties of the same
+  /// name in every updated value. Returns how many values were updated.
+  ///
+  /// Designed against a couple of database specifications. Including the
+  /// following:
+  ///
+  /// - [SQL `UPDATE` statement][1].
+  /// - [MongoDB `update` command][2].
+  ///
+  /// [1]: http://www.postgresql.org/docs/current/static/sql-update.html
+  /// [2]: https://docs.mongodb.org/manual/reference/command/update/
+  fn update(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    patch: Value
+  ) -> Result<u64, Error>;
+
+  /// Delete all of the values which pass the condition. Returns how many
+  /// values were deleted.
+  ///
+  /// Designed against a couple of database specifications. Including the
+  /// following:
+  ///
+  /// - [SQL `DELETE` statement][1].
+  /// - [MongoDB `delete` command][2].
+  ///
+  /// [1]: http://www.postgresql.org/docs/current/static/sql-delete.html
+  /// [2]: https://docs.mongodb.org/manual/reference/command/delete/
+  fn delete(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error>;
+
+  /// Count how many values pass the condition. The default implementation
+  /// reads every value which passes the condition and counts them.
+  ///
+  /// This method may be optionally optimized by the driver.
+  fn count(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    Ok(try!(self.read(
+      type_,
+      condition,
+      Default::default(),
+      Default::default(),
+      Query::All
+    )).count() as u64)
+  }
+
+  /// Group the values which pass the condition and summarize every group
+  /// with the accumulators of an aggregation. See `Aggregation` for the
+  /// format of the returned groups.
+  ///
+  /// Designed against a couple of database specifications. Including the
+  /// following:
+  ///
+  /// - [SQL `GROUP BY` clause][1].
+  /// - [MongoDB `$group` pipeline stage][2].
+  ///
+  /// The default implementation reads every value which passes the condition
+  /// and aggregates them in memory with `Aggregation::aggregate`.
+  ///
+  /// This method may be optionally optimized by the driver.
+  ///
+  /// [1]: http://www.postgresql.org/docs/current/static/sql-select.html#SQL-GROUPBY
+  /// [2]: https://docs.mongodb.org/manual/reference/operator/aggregation/group/
+  fn aggregate(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    aggregation: Aggregation
+  ) -> Result<ValueIter, Error> {
+    try!(aggregation.check());
+    let values: Vec<_> = try!(self.read(
+      type_,
+      condition,
+      Default::default(),
+      Default::default(),
+      Query::All
+    )).collect();
+    Ok(ValueIter::new(aggregation.aggregate(values.iter()).into_iter()))
+  }
+
+  /// Read some values from the driver along with the total number of values
+  /// which pass the condition, ignoring the range. Useful for pagination
+  /// interfaces which show how many pages there are.
+  ///
+  /// If the range skips beyond the total a `BadRange` error is returned.
+  fn read_with_total(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    range: Range,
+    query: Query
+  ) -> Result<(ValueIter, u64), Error> {
+    let total = try!(self.count(type_, condition.clone()));
+    try!(range.check(total));
+    Ok((try!(self.read(type_, condition, sort, range, query)), total))
+  }
+
   /// Read a single value from the driver. The default implementation uses the
   /// driver read method with a range of one.
   ///
@@ -64,4 +195,55 @@ pub trait Driver {
       Err(Error::new(NotFound, "No value was found for the condition."))
     }
   }
+
+  /// Read a page of values using cursor pagination instead of skipping. Along
+  /// with the values, a cursor pointing after the last value is returned if
+  /// the page is full. Passing that cursor as `after` reads the next page.
+  /// When `after` is `None` the first page is read.
+  ///
+  /// A cursor only exists for a sorted read with a limit, so cursor
+  /// pagination is its own method instead of `read` returning a cursor. This
+  /// way `read` keeps returning a plain `ValueIter` for range reads, and
+  /// drivers which do not optimize pagination need no changes.
+  ///
+  /// The default implementation adds the condition from `Cursor::condition`
+  /// to the driver read method, so pages stay stable even when values are
+  /// added or removed. Every value is read in full to get its sorted
+  /// properties before being selected with the query.
+  ///
+  /// This method may be optionally optimized by the driver.
+  fn read_page(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    limit: u64,
+    after: Option<Cursor>,
+    query: Query
+  ) -> Result<(ValueIter, Option<Cursor>), Error> {
+    try!(Cursor::check_sort(&sort));
+
+    let condition = match after {
+      Some(cursor) => Condition::And(vec![condition, try!(cursor.condition(&sort))]),
+      None => condition
+    };
+
+    let values: Vec<_> = try!(self.read(
+      type_,
+      condition,
+      sort.clone(),
+      Range::new(None, Some(limit)),
+      Query::All
+    )).collect();
+
+    let cursor = if values.len() as u64 == limit {
+      values.last().map(|value| Cursor::from_value(&sort, value))
+    } else {
+      None
+    };
+
+    let values: Vec<_> = values.iter().filter_map(|value| query.select(value)).collect();
+
+    Ok((ValueIter::new(values.into_iter()), cursor))
+  }
 }
@@ -0,0 +1,398 @@
+//! A driver which stores every type as a file in a directory. Useful for
+//! prototyping and fixtures, as no database needs to be running.
+//!
+//! A type is stored either as a [JSON Lines][1] file named `<type>.jsonl`,
+//! with one object per line, or as a YAML file named `<type>.yml` containing
+//! a sequence of objects. When both exist the JSON Lines file is used. New
+//! types are written as JSON Lines.
+//!
+//! Unsorted reads of a JSON Lines file check every line and then stream one
+//! line at a time. Sorted reads and YAML files are evaluated in memory with
+//! `read_values`. Writes
+//! replace the whole file by writing to a temporary file in the same
+//! directory and renaming it, so readers never see a partially written file.
+//!
+//! [1]: http://jsonlines.org
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::usize;
+
+use serde_yaml;
+use url::Url;
+
+use driver::{Driver, Connect};
+use driver::memory::read_values;
+use error::Error;
+use query::{Range, SortRule, Condition, Query};
+use schema::Type;
+use value::{Value, ValueIter};
+
+/// Counts the temporary files written by this process, so that concurrent
+/// writes never share a temporary file.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// The formats a type may be stored in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Format {
+  /// One JSON value per line.
+  JsonLines,
+  /// A single YAML sequence of values.
+  Yaml
+}
+
+impl Format {
+  /// The file extension used for the format.
+  fn extension(&self) -> &'static str {
+    match *self {
+      Format::JsonLines => "jsonl",
+      Format::Yaml => "yml"
+    }
+  }
+}
+
+/// A driver storing the values of every type in a file inside a single
+/// directory.
+pub struct FileDriver {
+  directory: PathBuf
+}
+
+/// Parses the line at an index of a JSON Lines file. Blank lines have no
+/// value.
+fn parse_line(path: &Path, index: usize, line: io::Result<String>) -> Result<Option<Value>, Error> {
+  let line = try!(line);
+  if line.trim().is_empty() {
+    return Ok(None);
+  }
+  Value::from_json(&line).map(Some).map_err(|_| Error::invalid(
+    format!("Could not parse line {} of '{}' as JSON.", index + 1, path.display()),
+    "Make sure every line of a JSON Lines file is a single JSON value."
+  ))
+}
+
+impl FileDriver {
+  /// Creates a new driver for a directory. The directory is not required to
+  /// exist until values are written.
+  pub fn new<P>(directory: P) -> Self where P: Into<PathBuf> {
+    FileDriver {
+      directory: directory.into()
+    }
+  }
+
+  /// Gets the path of the file for a type in a certain format.
+  fn path(&self, type_: &Type, format: Format) -> PathBuf {
+    self.directory.join(format!("{}.{}", type_.name(), format.extension()))
+  }
+
+  /// Finds the format and path of the file which currently stores a type.
+  /// Returns `None` if no file exists for the type.
+  fn find(&self, type_: &Type) -> Option<(Format, PathBuf)> {
+    [Format::JsonLines, Format::Yaml].iter()
+      .map(|format| (*format, self.path(type_, *format)))
+      .find(|&(_, ref path)| path.is_file())
+  }
+
+  /// Loads every value of a type. A type without a file has no values.
+  fn load(&self, type_: &Type) -> Result<Vec<Value>, Error> {
+    let (format, path) = match self.find(type_) {
+      Some(found) => found,
+      None => return Ok(Vec::new())
+    };
+
+    let mut reader = BufReader::new(try!(File::open(&path)));
+
+    match format {
+      Format::JsonLines => {
+        let mut values = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+          if let Some(value) = try!(parse_line(&path, i, line)) {
+            values.push(value);
+          }
+        }
+        Ok(values)
+      },
+      Format::Yaml => {
+        let mut yaml = String::new();
+        try!(reader.read_to_string(&mut yaml));
+        if yaml.trim().is_empty() {
+          Ok(Vec::new())
+        } else {
+          Ok(try!(serde_yaml::from_str(&yaml)))
+        }
+      }
+    }
+  }
+
+  /// Replaces every value of a type. The values are written in the format of
+  /// the existing file, or as JSON Lines if there is none. The file is
+  /// written atomically by renaming a temporary file over it.
+  fn save(&self, type_: &Type, values: &[Value]) -> Result<(), Error> {
+    let (format, path) = self.find(type_).unwrap_or_else(|| (Format::JsonLines, self.path(type_, Format::JsonLines)));
+
+    let contents = match format {
+      Format::JsonLines => {
+        let mut contents = String::new();
+        for value in values {
+          contents.push_str(&try!(value.to_json()));
+          contents.push('\n');
+        }
+        contents
+      },
+      Format::Yaml => try!(serde_yaml::to_string(&values))
+    };
+
+    try!(fs::create_dir_all(&self.directory));
+    let temp_path = self.directory.join(format!(
+      ".{}.{}.{}-{}.tmp",
+      type_.name(),
+      format.extension(),
+      process::id(),
+      TEMP_FILES.fetch_add(1, Ordering::SeqCst)
+    ));
+    {
+      let mut file = try!(File::create(&temp_path));
+      try!(file.write_all(contents.as_bytes()));
+      try!(file.sync_all());
+    }
+    try!(fs::rename(&temp_path, &path));
+    Ok(())
+  }
+}
+
+impl Connect for FileDriver {
+  /// Connects to a directory with a URL like `file:///path/to/data`.
+  fn connect(url: &Url) -> Result<Self, Error> {
+    match (url.scheme.as_str(), url.to_file_path()) {
+      ("file", Ok(directory)) => Ok(FileDriver::new(directory)),
+      _ => Err(Error::invalid(
+        format!("Cannot connect to '{}' with the file driver.", url),
+        "Try a URL with an absolute path like `file:///path/to/data`."
+      ))
+    }
+  }
+}
+
+impl Driver for FileDriver {
+  fn read(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    range: Range,
+    query: Query
+  ) -> Result<ValueIter, Error> {
+    // Without a sort, values are streamed from a JSON Lines file one line at
+    // a time. A `ValueIter` cannot return an error, so every line is checked
+    // before streaming. Writes rename a new file over the path, so the open
+    // file does not change between the two passes.
+    if sort.is_empty() {
+      if let Some((Format::JsonLines, path)) = self.find(type_) {
+        let mut file = try!(File::open(&path));
+        for (i, line) in BufReader::new(&file).lines().enumerate() {
+          try!(parse_line(&path, i, line));
+        }
+        try!(file.seek(SeekFrom::Start(0)));
+        let values = BufReader::new(file).lines().enumerate()
+          .filter_map(move |(i, line)| parse_line(&path, i, line).ok().and_then(|value| value))
+          .filter(move |value| condition.is_true(value))
+          .skip(range.skip().unwrap_or(0) as usize)
+          .take(range.limit().map_or(usize::MAX, |limit| limit as usize))
+          .filter_map(move |value| query.select(&value));
+        return Ok(ValueIter::new(values));
+      }
+    }
+
+    let values = try!(self.load(type_));
+    Ok(ValueIter::new(read_values(values.iter(), condition, sort, range, query).into_iter()))
+  }
+
+  fn create(
+    &self,
+    type_: &Type,
+    values: Vec<Value>
+  ) -> Result<Vec<Value>, Error> {
+    for value in &values {
+      match *value {
+        Value::Object(_) => {},
+        _ => return Err(Error::invalid(
+          format!("Cannot create a non-object value in type '{}'.", type_.name()),
+          "Try creating an object value instead."
+        ))
+      }
+    }
+    let mut stored = try!(self.load(type_));
+    stored.extend(values.iter().cloned());
+    try!(self.save(type_, &stored));
+    Ok(values)
+  }
+
+  fn update(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    patch: Value
+  ) -> Result<u64, Error> {
+    let patch = match patch {
+      Value::Object(patch) => patch,
+      _ => return Err(Error::invalid(
+        format!("Cannot update values in type '{}' with a non-object patch.", type_.name()),
+        "Try using an object patch instead."
+      ))
+    };
+    let mut values = try!(self.load(type_));
+    let mut count = 0;
+    for value in values.iter_mut().filter(|value| condition.is_true(value)) {
+      if let Value::Object(ref mut object) = *value {
+        for (key, property) in patch.iter() {
+          object.insert(key.to_owned(), property.clone());
+        }
+      }
+      count += 1;
+    }
+    if count > 0 {
+      try!(self.save(type_, &values));
+    }
+    Ok(count)
+  }
+
+  fn delete(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    let mut values = try!(self.load(type_));
+    let length = values.len();
+    values.retain(|value| !condition.is_true(value));
+    let count = (length - values.len()) as u64;
+    if count > 0 {
+      try!(self.save(type_, &values));
+    }
+    Ok(count)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs::{self, File};
+  use std::io::Write;
+  use std::path::PathBuf;
+
+  use url::Url;
+
+  use driver::{Driver, Connect};
+  use driver::file::FileDriver;
+  use driver::testing::{read, temp_directory};
+  use query::{Range, SortRule, Condition, Query};
+  use schema::Type;
+  use value::Value;
+
+  fn get_type(name: &str) -> Type {
+    let mut type_ = Type::new();
+    type_.set_name(name);
+    type_
+  }
+
+  fn write(path: PathBuf, contents: &str) {
+    File::create(path).unwrap().write_all(contents.as_bytes()).unwrap();
+  }
+
+  #[test]
+  fn test_connect() {
+    assert!(FileDriver::connect(&Url::parse("file:///tmp/ardite").unwrap()).is_ok());
+    FileDriver::connect(&Url::parse("sqlite::memory:").unwrap()).err().unwrap().expect("Cannot connect");
+  }
+
+  #[test]
+  fn test_read_json_lines() {
+    let directory = temp_directory("file", "read-json-lines");
+    write(directory.join("person.jsonl"), "{\"name\":\"Ada\",\"age\":36}\n\n{\"name\":\"Alan\",\"age\":41}\n");
+    let driver = FileDriver::new(directory);
+    let type_ = get_type("person");
+    assert_eq!(
+      read(&driver, &type_, Default::default(), vec![], Default::default(), Default::default()),
+      vec![value!({ "name" => "Ada", "age" => 36 }), value!({ "name" => "Alan", "age" => 41 })]
+    );
+    assert_eq!(
+      read(&driver, &type_, Condition::Keys(linear_map! { str!("age") => Condition::GreaterThan(Value::I64(40)) }), vec![], Default::default(), Query::Keys(linear_map! {
+        str!("name") => Query::All
+      })),
+      vec![value!({ "name" => "Alan" })]
+    );
+    assert_eq!(read(&driver, &get_type("other"), Default::default(), vec![], Default::default(), Default::default()), vec![]);
+  }
+
+  #[test]
+  fn test_read_yaml() {
+    let directory = temp_directory("file", "read-yaml");
+    write(directory.join("person.yml"), "- name: Ada\n  age: 36\n- name: Alan\n  age: 41\n");
+    let driver = FileDriver::new(directory);
+    assert_eq!(
+      read(&driver, &get_type("person"), Default::default(), vec![SortRule::new(point!["age"], false)], Range::new(None, Some(1)), Default::default()),
+      vec![value!({ "name" => "Alan", "age" => 41 })]
+    );
+  }
+
+  #[test]
+  fn test_read_invalid() {
+    let directory = temp_directory("file", "read-invalid");
+    write(directory.join("person.jsonl"), "{\"name\":\"Ada\"}\n{\"name\":\n{\"name\":\"Alan\"}\n");
+    let driver = FileDriver::new(directory);
+    let type_ = get_type("person");
+    driver.read(&type_, Default::default(), vec![SortRule::new(point!["name"], true)], Default::default(), Default::default()).err().unwrap().expect("line 2");
+    driver.read(&type_, Default::default(), vec![], Default::default(), Default::default()).err().unwrap().expect("line 2");
+  }
+
+  #[test]
+  fn test_read_stream() {
+    let directory = temp_directory("file", "read-stream");
+    write(directory.join("number.jsonl"), "{\"n\":1}\n{\"n\":2}\n{\"n\":3}\n{\"n\":4}\n{\"n\":5}\n");
+    let driver = FileDriver::new(directory);
+    assert_eq!(
+      read(&driver, &get_type("number"), Condition::Keys(linear_map! { str!("n") => Condition::GreaterThan(Value::I64(1)) }), vec![], Range::new(Some(1), Some(2)), Default::default()),
+      vec![value!({ "n" => 3 }), value!({ "n" => 4 })]
+    );
+  }
+
+  #[test]
+  fn test_write_temp_files() {
+    let directory = temp_directory("file", "write-temp-files");
+    let driver = FileDriver::new(directory.clone());
+    let type_ = get_type("person");
+    driver.create(&type_, vec![value!({ "name" => "Ada" })]).unwrap();
+    driver.create(&type_, vec![value!({ "name" => "Alan" })]).unwrap();
+    // Every temporary file is renamed away after it is written.
+    let files: Vec<_> = fs::read_dir(&directory).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(files.len(), 1);
+    assert_eq!(driver.count(&type_, Condition::True).unwrap(), 2);
+  }
+
+  #[test]
+  fn test_write() {
+    let directory = temp_directory("file", "write");
+    let driver = FileDriver::new(directory.clone());
+    let type_ = get_type("person");
+    driver.create(&type_, vec![value!({ "name" => "Ada" }), value!({ "name" => "Alan" })]).unwrap();
+    driver.create(&type_, vec![value!(7)]).unwrap_err().expect("non-object value");
+    assert!(directory.join("person.jsonl").is_file());
+    assert_eq!(driver.update(&type_, Condition::Keys(linear_map! { str!("name") => Condition::Equal(Value::String(str!("Ada"))) }), value!({ "age" => 36 })).unwrap(), 1);
+    assert_eq!(driver.delete(&type_, Condition::Keys(linear_map! { str!("name") => Condition::Equal(Value::String(str!("Alan"))) })).unwrap(), 1);
+    assert_eq!(
+      read(&FileDriver::new(directory), &type_, Default::default(), vec![], Default::default(), Default::default()),
+      vec![value!({ "name" => "Ada", "age" => 36 })]
+    );
+  }
+
+  #[test]
+  fn test_write_yaml() {
+    let directory = temp_directory("file", "write-yaml");
+    write(directory.join("person.yml"), "- name: Ada\n");
+    let driver = FileDriver::new(directory.clone());
+    let type_ = get_type("person");
+    driver.create(&type_, vec![value!({ "name" => "Alan" })]).unwrap();
+    assert!(!directory.join("person.jsonl").exists());
+    assert_eq!(driver.count(&type_, Condition::True).unwrap(), 2);
+  }
+}
@@ -0,0 +1,361 @@
+//! A driver which keeps all of its values in memory. Useful for tests and as
+//! a reference for how every query construct should behave.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::usize;
+
+use url::Url;
+
+use driver::{Driver, Connect};
+use error::Error;
+use query::{Range, SortRule, Condition, Query};
+use schema::Type;
+use value::{Key, Value, ValueIter};
+
+/// A driver storing `Value::Object`s in memory for every type name. Nothing
+/// is persisted, so all values are lost when the driver is dropped.
+pub struct MemoryDriver {
+  types: RefCell<BTreeMap<Key, Vec<Value>>>
+}
+
+impl MemoryDriver {
+  /// Creates a new driver with no values.
+  pub fn new() -> Self {
+    MemoryDriver {
+      types: RefCell::new(BTreeMap::new())
+    }
+  }
+}
+
+impl Connect for MemoryDriver {
+  /// The URL is ignored, every connection creates a new empty driver.
+  fn connect(_: &Url) -> Result<Self, Error> {
+    Ok(MemoryDriver::new())
+  }
+}
+
+impl Driver for MemoryDriver {
+  fn read(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    range: Range,
+    query: Query
+  ) -> Result<ValueIter, Error> {
+    let types = self.types.borrow();
+    let values = types.get(type_.name()).map_or(vec![], |values| read_values(values.iter(), condition, sort, range, query));
+    Ok(ValueIter::new(values.into_iter()))
+  }
+
+  fn count(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    let types = self.types.borrow();
+    Ok(types.get(type_.name()).map_or(0, |values| values.iter().filter(|value| condition.is_true(value)).count() as u64))
+  }
+
+  fn create(
+    &self,
+    type_: &Type,
+    values: Vec<Value>
+  ) -> Result<Vec<Value>, Error> {
+    for value in &values {
+      match *value {
+        Value::Object(_) => {},
+        _ => return Err(Error::invalid(
+          format!("Cannot create a non-object value in type '{}'.", type_.name()),
+          "Try creating an object value instead."
+        ))
+      }
+    }
+    self.types.borrow_mut().entry(type_.name().to_owned()).or_insert_with(Vec::new).extend(values.iter().cloned());
+    Ok(values)
+  }
+
+  fn update(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    patch: Value
+  ) -> Result<u64, Error> {
+    let patch = match patch {
+      Value::Object(patch) => patch,
+      _ => return Err(Error::invalid(
+        format!("Cannot update values in type '{}' with a non-object patch.", type_.name()),
+        "Try using an object patch instead."
+      ))
+    };
+    let mut count = 0;
+    if let Some(values) = self.types.borrow_mut().get_mut(type_.name()) {
+      for value in values.iter_mut().filter(|value| condition.is_true(value)) {
+        if let Value::Object(ref mut object) = *value {
+          for (key, property) in patch.iter() {
+            object.insert(key.to_owned(), property.clone());
+          }
+        }
+        count += 1;
+      }
+    }
+    Ok(count)
+  }
+
+  fn delete(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    let mut count = 0;
+    if let Some(values) = self.types.borrow_mut().get_mut(type_.name()) {
+      let length = values.len();
+      values.retain(|value| !condition.is_true(value));
+      count = (length - values.len()) as u64;
+    }
+    Ok(count)
+  }
+}
+
+/// Reads values from any iterator by filtering with the condition, sorting
+/// with the sort rules, skipping and limiting with the range, and finally
+/// selecting with the query. Drivers which must evaluate a read in memory
+/// should use this function.
+pub fn read_values<'a, I>(
+  values: I,
+  condition: Condition,
+  sort: Vec<SortRule>,
+  range: Range,
+  query: Query
+) -> Vec<Value> where I: Iterator<Item=&'a Value> {
+  let mut values: Vec<&Value> = values.filter(|value| condition.is_true(value)).collect();
+
+  // The sort is stable so values which compare equally stay in their original
+  // order.
+  values.sort_by(|a, b| {
+    for rule in &sort {
+      match rule.compare(a, b) {
+        Ordering::Equal => {},
+        ordering => return ordering
+      }
+    }
+    Ordering::Equal
+  });
+
+  values
+    .into_iter()
+    .skip(range.skip().unwrap_or(0) as usize)
+    .take(range.limit().map_or(usize::MAX, |limit| limit as usize))
+    .filter_map(|value| query.select(value))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use driver::Driver;
+  use driver::memory::MemoryDriver;
+  use driver::testing::read;
+  use error::{BadRange, BadRequest};
+  use query::{Range, SortRule, Condition, Query, Cursor, Aggregation, Accumulator};
+  use schema::Type;
+  use value::Value;
+
+  fn val_a() -> Value { value!({ "a" => 1, "b" => 2, "c" => 3, "d" => 4 }) }
+  fn val_b() -> Value { value!({ "b" => 2, "c" => 4, "hello" => "world", "doc_a" => { "a" => 1, "b" => 2, "c" => 3, "d" => 4 } }) }
+  fn val_c() -> Value { value!({ "a" => 1, "c" => 3, "doc_b" => { "b" => 2, "c" => 4, "hello" => "world", "doc_a" => { "a" => 1, "b" => 2, "c" => 3, "d" => 4 } } }) }
+
+  fn get_fixtures() -> (Type, MemoryDriver) {
+    let mut type_ = Type::new();
+    type_.set_name("test");
+    let driver = MemoryDriver::new();
+    driver.create(&type_, vec![val_a(), val_b(), val_c()]).unwrap();
+    (type_, driver)
+  }
+
+  #[test]
+  fn test_read_all() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Default::default(), Default::default()), vec![val_a(), val_b(), val_c()]);
+    let mut other_type = Type::new();
+    other_type.set_name("other");
+    assert_eq!(read(&driver, &other_type, Default::default(), vec![], Default::default(), Default::default()), vec![]);
+  }
+
+  #[test]
+  fn test_read_condition() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(read(&driver, &type_, Condition::False, vec![], Default::default(), Default::default()), vec![]);
+    assert_eq!(read(&driver, &type_, Condition::Or(vec![Condition::True, Condition::False]), vec![], Default::default(), Default::default()), vec![val_a(), val_b(), val_c()]);
+    assert_eq!(
+      read(&driver, &type_, Condition::Keys(linear_map! {
+        str!("c") => Condition::Equal(Value::I64(3))
+      }), vec![], Default::default(), Default::default()),
+      vec![val_a(), val_c()]
+    );
+    assert_eq!(
+      read(&driver, &type_, Condition::Keys(linear_map! {
+        str!("doc_b") => Condition::Keys(linear_map! {
+          str!("doc_a") => Condition::Keys(linear_map! {
+            str!("d") => Condition::Equal(Value::I64(4))
+          })
+        })
+      }), vec![], Default::default(), Default::default()),
+      vec![val_c()]
+    );
+  }
+
+  #[test]
+  fn test_read_sort() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(read(&driver, &type_, Default::default(), vec![SortRule::new(point!["c"], true)], Default::default(), Default::default()), vec![val_a(), val_c(), val_b()]);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![SortRule::new(point!["c"], false)], Default::default(), Default::default()), vec![val_b(), val_a(), val_c()]);
+    assert_eq!(
+      read(&driver, &type_, Default::default(), vec![SortRule::new(point!["c"], true), SortRule::new(point!["d"], true)], Default::default(), Default::default()),
+      vec![val_c(), val_a(), val_b()]
+    );
+  }
+
+  #[test]
+  fn test_read_range() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Range::new(None, Some(2)), Default::default()), vec![val_a(), val_b()]);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Range::new(Some(1), Some(1)), Default::default()), vec![val_b()]);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Range::new(Some(1), None), Default::default()), vec![val_b(), val_c()]);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Range::new(Some(2), Some(40)), Default::default()), vec![val_c()]);
+  }
+
+  #[test]
+  fn test_read_query() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(
+      read(&driver, &type_, Default::default(), vec![], Default::default(), Query::Keys(linear_map! {
+        str!("a") => Query::All,
+        str!("c") => Query::All,
+        str!("hello") => Query::All,
+        str!("doc_a") => Query::Keys(linear_map! {
+          str!("b") => Query::All
+        }),
+        str!("doc_b") => Query::Keys(linear_map! {
+          str!("hello") => Query::All,
+          str!("doc_a") => Query::Keys(linear_map! {
+            str!("b") => Query::All
+          })
+        })
+      })),
+      vec![
+        value!({ "a" => 1, "c" => 3 }),
+        value!({ "c" => 4, "hello" => "world", "doc_a" => { "b" => 2 } }),
+        value!({ "a" => 1, "c" => 3, "doc_b" => { "hello" => "world", "doc_a" => { "b" => 2 } } })
+      ]
+    );
+  }
+
+  #[test]
+  fn test_count() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(driver.count(&type_, Condition::True).unwrap(), 3);
+    assert_eq!(driver.count(&type_, Condition::Keys(linear_map! { str!("c") => Condition::Equal(Value::I64(3)) })).unwrap(), 2);
+    let mut other_type = Type::new();
+    other_type.set_name("other");
+    assert_eq!(driver.count(&other_type, Condition::True).unwrap(), 0);
+  }
+
+  #[test]
+  fn test_read_with_total() {
+    let (type_, driver) = get_fixtures();
+    let (values, total) = driver.read_with_total(&type_, Default::default(), vec![], Range::new(Some(1), Some(1)), Default::default()).unwrap();
+    assert_eq!(values.collect::<Vec<_>>(), vec![val_b()]);
+    assert_eq!(total, 3);
+    let error = driver.read_with_total(&type_, Default::default(), vec![], Range::new(Some(4), None), Default::default()).err().unwrap();
+    assert_eq!(error.code(), &BadRange);
+  }
+
+  #[test]
+  fn test_aggregate() {
+    let (type_, driver) = get_fixtures();
+    let mut aggregation = Aggregation::new(vec![point!["c"]]);
+    aggregation.add_accumulator("count", Accumulator::Count);
+    aggregation.add_accumulator("sum", Accumulator::Sum(point!["b"]));
+    assert_eq!(driver.aggregate(&type_, Condition::True, aggregation.clone()).unwrap().collect::<Vec<_>>(), vec![
+      value!({ "c" => 3, "count" => 2, "sum" => 2 }),
+      value!({ "c" => 4, "count" => 1, "sum" => 2 })
+    ]);
+    assert_eq!(
+      driver.aggregate(&type_, Condition::Keys(linear_map! { str!("a") => Condition::Exists }), aggregation).unwrap().collect::<Vec<_>>(),
+      vec![value!({ "c" => 3, "count" => 2, "sum" => 2 })]
+    );
+    let mut aggregation = Aggregation::new(vec![point!["c"]]);
+    aggregation.add_accumulator("c", Accumulator::Count);
+    assert_eq!(driver.aggregate(&type_, Condition::True, aggregation).err().unwrap().code(), &BadRequest);
+  }
+
+  #[test]
+  fn test_read_one() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(driver.read_one(&type_, Condition::Keys(linear_map! { str!("hello") => Condition::Equal(Value::String(str!("world"))) }), Query::All).unwrap(), val_b());
+    driver.read_one(&type_, Condition::False, Query::All).unwrap_err().expect("No value was found");
+  }
+
+  #[test]
+  fn test_read_page() {
+    let mut type_ = Type::new();
+    type_.set_name("page");
+    let driver = MemoryDriver::new();
+    driver.create(&type_, vec![
+      value!({ "id" => 1, "n" => "a" }),
+      value!({ "id" => 2, "n" => "b" }),
+      value!({ "id" => 3, "n" => "a" }),
+      value!({ "id" => 4, "n" => "b" })
+    ]).unwrap();
+    let sort = vec![SortRule::new(point!["n"], true), SortRule::new(point!["id"], false)];
+    let read_page = |after: Option<Cursor>| {
+      let (values, cursor) = driver.read_page(&type_, Default::default(), sort.clone(), 2, after, Query::Keys(linear_map! {
+        str!("id") => Query::All
+      })).unwrap();
+      (values.collect::<Vec<_>>(), cursor)
+    };
+    let (values, cursor) = read_page(None);
+    assert_eq!(values, vec![value!({ "id" => 3 }), value!({ "id" => 1 })]);
+    assert_eq!(cursor, Some(Cursor::new(vec![value!("a"), value!(1)])));
+    // A value created before the cursor does not change the next page.
+    driver.create(&type_, vec![value!({ "id" => 5, "n" => "a" })]).unwrap();
+    let (values, cursor) = read_page(cursor);
+    assert_eq!(values, vec![value!({ "id" => 4 }), value!({ "id" => 2 })]);
+    assert_eq!(cursor, Some(Cursor::new(vec![value!("b"), value!(2)])));
+    assert_eq!(read_page(cursor), (vec![], None));
+    driver.read_page(&type_, Default::default(), vec![], 2, None, Query::All).unwrap_err().expect("without any sort rules");
+  }
+
+  #[test]
+  fn test_create() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(driver.create(&type_, vec![value!({ "a" => 5 })]).unwrap(), vec![value!({ "a" => 5 })]);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Range::new(Some(3), None), Default::default()), vec![value!({ "a" => 5 })]);
+    driver.create(&type_, vec![value!({ "a" => 6 }), value!(7)]).unwrap_err().expect("non-object value");
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Default::default(), Default::default()).len(), 4);
+  }
+
+  #[test]
+  fn test_update() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(driver.update(&type_, Condition::Keys(linear_map! { str!("c") => Condition::Equal(Value::I64(3)) }), value!({ "c" => 5, "e" => "hello" })).unwrap(), 2);
+    assert_eq!(
+      read(&driver, &type_, Condition::Keys(linear_map! { str!("c") => Condition::Equal(Value::I64(5)) }), vec![], Default::default(), Query::Keys(linear_map! {
+        str!("a") => Query::All,
+        str!("e") => Query::All
+      })),
+      vec![value!({ "a" => 1, "e" => "hello" }), value!({ "a" => 1, "e" => "hello" })]
+    );
+    driver.update(&type_, Condition::True, value!(5)).unwrap_err().expect("non-object patch");
+  }
+
+  #[test]
+  fn test_delete() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(driver.delete(&type_, Condition::Keys(linear_map! { str!("c") => Condition::Equal(Value::I64(3)) })).unwrap(), 2);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Default::default(), Default::default()), vec![val_b()]);
+    assert_eq!(driver.delete(&type_, Condition::False).unwrap(), 0);
+  }
+}
@@ -2,7 +2,21 @@
 //! different drivers exist elsewhere.
 
 mod driver;
+#[cfg(feature = "driver_csv")]
+pub mod csv;
+pub mod file;
+pub mod memory;
 #[cfg(feature = "driver_mongodb")]
 pub mod mongodb;
+#[cfg(feature = "driver_sqlite")]
+pub mod sqlite;
+mod registry;
+mod relation;
+mod router;
+#[cfg(test)]
+mod testing;
 
-pub use driver::driver::Driver;
+pub use driver::driver::{Driver, Connect};
+pub use driver::registry::{DriverRegistry, DriverFactory};
+pub use driver::relation::read_related;
+pub use driver::router::DriverRouter;
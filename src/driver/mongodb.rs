@@ -9,19 +9,20 @@ use mongodb::db::{Database, ThreadedDatabase};
 use mongodb::error::Error as MongoDBError;
 use url::Url;
 
-use driver::Driver;
+use driver::{Driver, Connect};
 use error::Error;
-use query::{Range, SortRule, Condition, Query};
+use query::{Range, SortRule, Condition, Query, Aggregation, Accumulator};
 use schema::Type;
 use value::{Key, Pointer, Value, ValueIter};
 
-struct MongoDriver {
+pub struct MongoDriver {
   database: Database
 }
 
-impl Driver for MongoDriver {
+impl Connect for MongoDriver {
   fn connect(url: &Url) -> Result<Self, Error> {
-    let config = try!(connstring::parse(uri));
+    let uri = url.to_string();
+    let config = try!(connstring::parse(&uri));
 
     if let Some(db_name) = config.database.clone() {
       Ok(MongoDriver {
@@ -34,7 +35,9 @@ impl Driver for MongoDriver {
       ))
     }
   }
+}
 
+impl Driver for MongoDriver {
   fn read(
     &self,
     type_: &Type,
@@ -79,6 +82,76 @@ impl Driver for MongoDriver {
 
     Ok(ValueIter::new(cursor.filter_map(Result::ok).map(Value::from)))
   }
+
+  /// Uses the MongoDB [`count` command][1] instead of reading every value.
+  ///
+  /// [1]: https://docs.mongodb.org/manual/reference/command/count/
+  fn count(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    let count = try!(self.database.collection(type_.name()).count(
+      Some(condition_to_filter_document(condition)),
+      None
+    ));
+    Ok(count as u64)
+  }
+
+  /// Uses a MongoDB [aggregation pipeline][1] with a `$group` stage instead
+  /// of aggregating in memory.
+  ///
+  /// [1]: https://docs.mongodb.org/manual/core/aggregation-pipeline/
+  fn aggregate(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    aggregation: Aggregation
+  ) -> Result<ValueIter, Error> {
+    try!(aggregation.check());
+    let cursor = try!(self.database.collection(type_.name()).aggregate(
+      aggregation_to_pipeline(condition, &aggregation),
+      None
+    ));
+    Ok(ValueIter::new(cursor.filter_map(Result::ok).map(move |document| group_to_value(&aggregation, document))))
+  }
+
+  fn create(
+    &self,
+    type_: &Type,
+    values: Vec<Value>
+  ) -> Result<Vec<Value>, Error> {
+    let documents = values.iter().cloned().map(Value::into).collect();
+    try!(self.database.collection(type_.name()).insert_many(documents, None));
+    Ok(values)
+  }
+
+  fn update(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    patch: Value
+  ) -> Result<u64, Error> {
+    let patch: Document = patch.into();
+    let result = try!(self.database.collection(type_.name()).update_many(
+      condition_to_filter_document(condition),
+      doc! { "$set" => (Bson::Document(patch)) },
+      None
+    ));
+    Ok(result.matched_count as u64)
+  }
+
+  fn delete(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    let result = try!(self.database.collection(type_.name()).delete_many(
+      condition_to_filter_document(condition),
+      None
+    ));
+    Ok(result.deleted_count as u64)
+  }
 }
 
 impl From<MongoDBError> for Error {
@@ -199,10 +272,47 @@ fn condition_to_filter(condition: Condition) -> Bson {
     Condition::Equal(value) => {
       let bson_value: Bson = value.into();
       bson!({ "$eq" => bson_value })
+    },
+    Condition::GreaterThan(value) => {
+      let bson_value: Bson = value.into();
+      bson!({ "$gt" => bson_value })
+    },
+    Condition::GreaterThanOrEqual(value) => {
+      let bson_value: Bson = value.into();
+      bson!({ "$gte" => bson_value })
+    },
+    Condition::LessThan(value) => {
+      let bson_value: Bson = value.into();
+      bson!({ "$lt" => bson_value })
+    },
+    Condition::LessThanOrEqual(value) => {
+      let bson_value: Bson = value.into();
+      bson!({ "$lte" => bson_value })
+    },
+    Condition::In(values) => bson!({
+      "$in" => (Bson::Array(values.into_iter().map(Value::into).collect()))
+    }),
+    Condition::NotIn(values) => bson!({
+      "$nin" => (Bson::Array(values.into_iter().map(Value::into).collect()))
+    }),
+    Condition::Exists => bson!({ "$exists" => true }),
+    Condition::Matches(regex) => bson!({ "$regex" => (regex.as_str()) }),
+    Condition::Contains(value) => {
+      let bson_value: Bson = value.into();
+      bson!({ "$elemMatch" => { "$eq" => bson_value } })
     }
   }
 }
 
+/// Transforms an Ardite condition to a MongoDB filter document. Commands like
+/// `update` and `delete` require the filter to be a document.
+fn condition_to_filter_document(condition: Condition) -> Document {
+  match condition_to_filter(condition) {
+    Bson::Document(document) => document,
+    _ => Document::new()
+  }
+}
+
 /// Transform an Ardite sort to a MongoDB sort.
 fn sort_rules_to_sort(sort_rules: Vec<SortRule>) -> Bson {
   let mut document = Document::new();
@@ -212,6 +322,62 @@ fn sort_rules_to_sort(sort_rules: Vec<SortRule>) -> Bson {
   Bson::Document(document)
 }
 
+/// Transform an Ardite pointer to a MongoDB field path expression.
+fn pointer_to_field_path(pointer: &Pointer) -> Bson {
+  Bson::String(format!("${}", pointer.join(".")))
+}
+
+/// Transform an Ardite aggregation to a MongoDB aggregation pipeline. Every
+/// grouped property is named by its index in the `_id` of the `$group` stage.
+fn aggregation_to_pipeline(condition: Condition, aggregation: &Aggregation) -> Vec<Document> {
+  let mut pipeline = Vec::new();
+
+  // The `$where` operator used for `Condition::True` is not allowed in a
+  // `$match` stage, so we leave out the stage instead.
+  if condition != Condition::True {
+    pipeline.push(doc! { "$match" => (condition_to_filter(condition)) });
+  }
+
+  let mut id = Document::new();
+  for (i, pointer) in aggregation.group_by().iter().enumerate() {
+    id.insert(format!("g{}", i), pointer_to_field_path(pointer));
+  }
+
+  let mut group = Document::new();
+  group.insert("_id", Bson::Document(id));
+  for (name, accumulator) in aggregation.accumulators().iter() {
+    group.insert(name.to_owned(), match *accumulator {
+      Accumulator::Count => bson!({ "$sum" => 1 }),
+      Accumulator::Sum(ref pointer) => bson!({ "$sum" => (pointer_to_field_path(pointer)) }),
+      Accumulator::Min(ref pointer) => bson!({ "$min" => (pointer_to_field_path(pointer)) }),
+      Accumulator::Max(ref pointer) => bson!({ "$max" => (pointer_to_field_path(pointer)) }),
+      Accumulator::Average(ref pointer) => bson!({ "$avg" => (pointer_to_field_path(pointer)) })
+    });
+  }
+
+  pipeline.push(doc! { "$group" => (Bson::Document(group)) });
+  pipeline
+}
+
+/// Transform a document from a `$group` stage to an Ardite group value.
+/// Grouped properties missing from the `_id` are `null`.
+fn group_to_value(aggregation: &Aggregation, mut document: Document) -> Value {
+  let id = match document.remove("_id") {
+    Some(Bson::Document(id)) => id,
+    _ => Document::new()
+  };
+
+  let group = (0..aggregation.group_by().len())
+    .map(|i| id.get(&format!("g{}", i)).cloned().map_or(Value::Null, Value::from))
+    .collect();
+
+  let accumulated = aggregation.accumulators().keys()
+    .map(|name| document.remove(name).map_or(Value::Null, Value::from))
+    .collect();
+
+  aggregation.to_group_value(group, accumulated)
+}
+
 /// Transform an Ardite query to a MongoDB projection.
 fn query_to_projection(query: Query) -> Bson {
   // The `add_keys` function is so that we can have a flat document with
@@ -242,14 +408,15 @@ fn query_to_projection(query: Query) -> Bson {
 
 #[cfg(test)]
 mod tests {
-  use super::{query_to_projection, sort_rules_to_sort, condition_to_filter};
+  use super::{query_to_projection, sort_rules_to_sort, condition_to_filter, aggregation_to_pipeline};
 
   use bson::{Bson, Document};
   use mongodb::db::ThreadedDatabase;
+  use regex::Regex;
 
-  use driver::Driver;
+  use driver::{Driver, Connect};
   use driver::mongodb::MongoDriver;
-  use query::{Range, SortRule, Condition, Query};
+  use query::{Range, SortRule, Condition, Query, Aggregation, Accumulator};
   use schema::{Definition, Type, Schema};
   use value::Value;
 
@@ -292,6 +459,33 @@ mod tests {
     assert_eq!(condition_to_filter(condition), filter);
   }
 
+  #[test]
+  fn test_condition_to_filter_operators() {
+    let condition = Condition::Keys(linear_map! {
+      str!("a") => Condition::GreaterThan(Value::I64(1)),
+      str!("b") => Condition::GreaterThanOrEqual(Value::F64(2.5)),
+      str!("c") => Condition::LessThan(Value::I64(3)),
+      str!("d") => Condition::LessThanOrEqual(Value::I64(4)),
+      str!("e") => Condition::In(vec![Value::String(str!("x")), Value::String(str!("y"))]),
+      str!("f") => Condition::NotIn(vec![Value::I64(5)]),
+      str!("g") => Condition::Exists,
+      str!("h") => Condition::Matches(Regex::new("^hello").unwrap()),
+      str!("i") => Condition::Contains(Value::Boolean(true))
+    });
+    let filter = bson!({
+      "a" => { "$gt" => 1i64 },
+      "b" => { "$gte" => 2.5 },
+      "c" => { "$lt" => 3i64 },
+      "d" => { "$lte" => 4i64 },
+      "e" => { "$in" => ["x", "y"] },
+      "f" => { "$nin" => [5i64] },
+      "g" => { "$exists" => true },
+      "h" => { "$regex" => "^hello" },
+      "i" => { "$elemMatch" => { "$eq" => true } }
+    });
+    assert_eq!(condition_to_filter(condition), filter);
+  }
+
   #[test]
   fn test_sort_rules_to_sort() {
     let sort = vec![
@@ -302,6 +496,32 @@ mod tests {
     assert_eq!(sort_rules_to_sort(sort), sort_bson);
   }
 
+  #[test]
+  fn test_aggregation_to_pipeline() {
+    let mut aggregation = Aggregation::new(vec![point!["a", "b"], point!["c"]]);
+    aggregation.add_accumulator("count", Accumulator::Count);
+    aggregation.add_accumulator("sum", Accumulator::Sum(point!["d"]));
+    aggregation.add_accumulator("min", Accumulator::Min(point!["d"]));
+    aggregation.add_accumulator("max", Accumulator::Max(point!["d"]));
+    aggregation.add_accumulator("avg", Accumulator::Average(point!["e", "f"]));
+    assert_eq!(aggregation_to_pipeline(Condition::True, &aggregation), vec![
+      doc! {
+        "$group" => {
+          "_id" => { "g0" => "$a.b", "g1" => "$c" },
+          "count" => { "$sum" => 1 },
+          "sum" => { "$sum" => "$d" },
+          "min" => { "$min" => "$d" },
+          "max" => { "$max" => "$d" },
+          "avg" => { "$avg" => "$e.f" }
+        }
+      }
+    ]);
+    assert_eq!(aggregation_to_pipeline(Condition::Keys(linear_map! { str!("c") => Condition::Exists }), &Aggregation::new(vec![])), vec![
+      doc! { "$match" => { "c" => { "$exists" => true } } },
+      doc! { "$group" => { "_id" => {} } }
+    ]);
+  }
+
   #[test]
   fn test_query_to_projection() {
     let query = Query::Keys(linear_map! {
@@ -605,4 +825,108 @@ mod tests {
       ]
     );
   }
+
+  #[test]
+  fn test_create() {
+    let fixtures = get_fixtures("create");
+    assert_eq!(
+      fixtures.driver.create(
+        fixtures.find_type(),
+        vec![value!({ "a" => 5 }), value!({ "a" => 6, "b" => true })]
+      ).unwrap(),
+      vec![value!({ "a" => 5 }), value!({ "a" => 6, "b" => true })]
+    );
+    assert_eq!(
+      fixtures.driver.read(
+        fixtures.find_type(),
+        Default::default(),
+        Default::default(),
+        Range::new(Some(3), None),
+        Default::default()
+      ).unwrap().collect::<Vec<Value>>(),
+      vec![value!({ "a" => 5 }), value!({ "a" => 6, "b" => true })]
+    );
+  }
+
+  #[test]
+  fn test_update() {
+    let fixtures = get_fixtures("update");
+    assert_eq!(
+      fixtures.driver.update(
+        fixtures.find_type(),
+        Condition::Keys(linear_map! {
+          str!("c") => Condition::Equal(Value::I64(3))
+        }),
+        value!({ "c" => 5, "e" => "hello" })
+      ).unwrap(),
+      2
+    );
+    assert_eq!(
+      fixtures.driver.read(
+        fixtures.find_type(),
+        Condition::Keys(linear_map! {
+          str!("c") => Condition::Equal(Value::I64(5))
+        }),
+        Default::default(),
+        Default::default(),
+        Query::Keys(linear_map! {
+          str!("a") => Query::All,
+          str!("e") => Query::All
+        })
+      ).unwrap().collect::<Vec<Value>>(),
+      vec![value!({ "a" => 1, "e" => "hello" }), value!({ "a" => 1, "e" => "hello" })]
+    );
+  }
+
+  #[test]
+  fn test_delete() {
+    let fixtures = get_fixtures("delete");
+    assert_eq!(
+      fixtures.driver.delete(
+        fixtures.find_type(),
+        Condition::Keys(linear_map! {
+          str!("c") => Condition::Equal(Value::I64(3))
+        })
+      ).unwrap(),
+      2
+    );
+    assert_eq!(
+      fixtures.driver.read(
+        fixtures.find_type(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        Default::default()
+      ).unwrap().collect::<Vec<Value>>(),
+      vec![val_b()]
+    );
+  }
+
+  #[test]
+  fn test_count() {
+    let fixtures = get_fixtures("count");
+    assert_eq!(fixtures.driver.count(fixtures.find_type(), Condition::True).unwrap(), 3);
+    assert_eq!(
+      fixtures.driver.count(
+        fixtures.find_type(),
+        Condition::Keys(linear_map! {
+          str!("c") => Condition::Equal(Value::I64(3))
+        })
+      ).unwrap(),
+      2
+    );
+  }
+
+  #[test]
+  fn test_aggregate() {
+    let fixtures = get_fixtures("aggregate");
+    let mut aggregation = Aggregation::new(vec![point!["c"]]);
+    aggregation.add_accumulator("count", Accumulator::Count);
+    let mut groups = fixtures.driver.aggregate(fixtures.find_type(), Condition::True, aggregation).unwrap().collect::<Vec<Value>>();
+    groups.sort_by(|a, b| SortRule::new(point!["c"], true).compare(a, b));
+    assert_eq!(groups, vec![
+      value!({ "c" => 3, "count" => 2 }),
+      value!({ "c" => 4, "count" => 1 })
+    ]);
+  }
 }
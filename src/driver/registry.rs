@@ -0,0 +1,173 @@
+//! Maps the scheme of a `DriverConfig` URL, like `mongodb` or `sqlite`, to
+//! the driver which should be connected for it.
+
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use url::Url;
+
+use driver::{Driver, Connect, DriverRouter};
+use driver::file::FileDriver;
+use driver::memory::MemoryDriver;
+use error::Error;
+use schema::{Definition, DriverConfig};
+
+/// A function which connects a driver to a URL.
+pub type DriverFactory = fn(&Url) -> Result<Rc<Driver>, Error>;
+
+/// A registry of driver factories keyed by URL scheme. A driver may be
+/// registered under more than one scheme.
+pub struct DriverRegistry {
+  factories: BTreeMap<String, DriverFactory>
+}
+
+impl DriverRegistry {
+  /// Creates a new registry without any drivers.
+  pub fn new() -> Self {
+    DriverRegistry {
+      factories: BTreeMap::new()
+    }
+  }
+
+  /// Creates a new registry with every driver built with `ardite-core`.
+  /// Drivers behind a feature are only registered when the feature is
+  /// enabled.
+  pub fn with_builtin_drivers() -> Self {
+    let mut registry = DriverRegistry::new();
+    registry.add_driver::<MemoryDriver>(&["memory"]);
+    registry.add_driver::<FileDriver>(&["file"]);
+    #[cfg(feature = "driver_mongodb")]
+    registry.add_driver::<::driver::mongodb::MongoDriver>(&["mongodb"]);
+    #[cfg(feature = "driver_sqlite")]
+    registry.add_driver::<::driver::sqlite::SqliteDriver>(&["sqlite"]);
+    #[cfg(feature = "driver_csv")]
+    registry.add_driver::<::driver::csv::CsvDriver>(&["csv"]);
+    registry
+  }
+
+  /// Registers a driver type under some schemes using `Connect::connect`.
+  pub fn add_driver<D>(&mut self, schemes: &[&str]) where D: Connect + 'static {
+    self.add_factory(schemes, connect_shared::<D>);
+  }
+
+  /// Registers a factory under some schemes. A factory registered under a
+  /// scheme which is already taken replaces the previous factory.
+  pub fn add_factory(&mut self, schemes: &[&str], factory: DriverFactory) {
+    for scheme in schemes {
+      self.factories.insert((*scheme).to_owned(), factory);
+    }
+  }
+
+  /// Gets the schemes which have a registered factory, in order.
+  pub fn schemes(&self) -> Vec<&str> {
+    self.factories.keys().map(String::as_str).collect()
+  }
+
+  /// Connects the driver registered for the scheme of the config URL.
+  pub fn connect(&self, config: &DriverConfig) -> Result<Rc<Driver>, Error> {
+    let scheme = &config.url().scheme;
+    match self.factories.get(scheme) {
+      Some(factory) => factory(config.url()),
+      None => Err(
+        Error::unimplemented(format!("No driver is registered for the '{}' URL scheme in '{}'.", scheme, config.url()))
+        .set_hint(format!(
+          "Try using one of the registered schemes ({}), or enabling the feature for the driver like `driver_{}`.",
+          self.schemes().join(", "),
+          scheme
+        ))
+      )
+    }
+  }
+
+  /// Connects a driver for every type in the definition and routes the type
+  /// to it. A type uses its own driver config, or the default config of the
+  /// definition if it has none. Types with the same config URL share a single
+  /// connected driver.
+  pub fn resolve(&self, definition: &Definition) -> Result<DriverRouter, Error> {
+    let mut connected: BTreeMap<String, Rc<Driver>> = BTreeMap::new();
+    let mut router = DriverRouter::new();
+
+    for (name, type_) in definition.types() {
+      let config = match type_.driver().or(definition.driver()) {
+        Some(config) => config,
+        None => return Err(Error::invalid(
+          format!("No driver is configured for type '{}'.", name),
+          "Try setting a `driver` URL for the type, or a default `driver` URL for the whole definition."
+        ))
+      };
+
+      let url = config.url().to_string();
+      let driver = match connected.get(&url) {
+        Some(driver) => driver.clone(),
+        None => try!(self.connect(config))
+      };
+      connected.insert(url, driver.clone());
+      router.add_shared_driver(name.to_owned(), driver);
+    }
+
+    Ok(router)
+  }
+}
+
+/// Connects a driver and wraps it in an `Rc` so that drivers of different
+/// types may be returned by the same factory type.
+fn connect_shared<D>(url: &Url) -> Result<Rc<Driver>, Error> where D: Connect + 'static {
+  Ok(Rc::new(try!(D::connect(url))))
+}
+
+#[cfg(test)]
+mod tests {
+  use url::Url;
+
+  use driver::Driver;
+  use driver::registry::DriverRegistry;
+  use driver::memory::MemoryDriver;
+  use error::NotImplemented;
+  use query::Condition;
+  use schema::{Definition, Type, DriverConfig};
+
+  fn config(url: &str) -> DriverConfig {
+    DriverConfig::new(Url::parse(url).unwrap())
+  }
+
+  #[test]
+  fn test_connect() {
+    let mut registry = DriverRegistry::new();
+    registry.add_driver::<MemoryDriver>(&["memory", "mem"]);
+    assert_eq!(registry.schemes(), vec!["mem", "memory"]);
+    assert!(registry.connect(&config("memory:")).is_ok());
+    assert!(registry.connect(&config("mem:")).is_ok());
+    let error = registry.connect(&config("party://fun:4242")).err().unwrap();
+    assert_eq!(error.code(), &NotImplemented);
+    error.expect("No driver is registered for the 'party' URL scheme");
+    assert!(error.hint().unwrap().contains("mem, memory"));
+  }
+
+  #[test]
+  fn test_resolve() {
+    let registry = DriverRegistry::with_builtin_drivers();
+    let mut definition = Definition::new();
+    definition.set_driver(config("memory:"));
+    definition.add_type("a", Type::new());
+    definition.add_type("b", {
+      let mut type_ = Type::new();
+      type_.set_driver(config("memory:other"));
+      type_
+    });
+    definition.add_type("c", Type::new());
+    let router = registry.resolve(&definition).unwrap();
+    assert_eq!(router.names(), vec!["a", "b", "c"]);
+    let type_a = definition.get_type(&str!("a")).unwrap();
+    router.create(type_a, vec![value!({ "hello" => "world" })]).unwrap();
+    // Types with the same driver config share a driver.
+    assert_eq!(router.get_driver("c").unwrap().count(type_a, Condition::True).unwrap(), 1);
+    assert_eq!(router.get_driver("b").unwrap().count(type_a, Condition::True).unwrap(), 0);
+  }
+
+  #[test]
+  fn test_resolve_without_driver() {
+    let mut definition = Definition::new();
+    definition.add_type("a", Type::new());
+    DriverRegistry::with_builtin_drivers().resolve(&definition).err().unwrap().expect("No driver is configured for type 'a'.");
+  }
+}
@@ -0,0 +1,303 @@
+//! Follows `$type` references when reading. A reference is stored as the key
+//! of the referenced value, and when a query selects properties of a
+//! reference the referenced value is read and spliced in place of the key.
+//!
+//! As described in `docs/driver.md`, graph behaviour is not the job of a
+//! driver. This module is a layer over `Driver::read` which loads the
+//! referenced values of every read value with a single read for each
+//! referencing property, instead of a read for every value.
+
+use std::cmp::Ordering;
+
+use linear_map::LinearMap;
+
+use driver::Driver;
+use error::Error;
+use query::{Condition, SortRule, Range, Query};
+use schema::{Definition, Type, Schema, SchemaArray, SchemaObject, SchemaReference};
+use value::{Key, Value, ValueIter};
+
+/// Reads some values from the driver and resolves every reference selected
+/// into by the query. Takes the same arguments as `Driver::read` along with
+/// the definition which the referenced types are found in.
+pub fn read_related<'a>(
+  driver: &'a Driver,
+  definition: &Definition,
+  type_: &Type,
+  condition: Condition,
+  sort: Vec<SortRule>,
+  range: Range,
+  query: Query
+) -> Result<ValueIter<'a>, Error> {
+  let driver_query = reference_keys_query(type_.schema(), &query);
+  let mut values: Vec<Value> = try!(driver.read(type_, condition, sort, range, driver_query)).collect();
+  try!(splice_references(driver, definition, type_.schema(), &query, values.iter_mut().collect()));
+  Ok(ValueIter::new(values.into_iter()))
+}
+
+/// Gets the schema nested directly in a schema at a key without looking
+/// through references, unlike `Schema::get`.
+fn get_child<'a>(schema: &'a Schema, key: &str) -> Option<&'a Schema> {
+  if let Some(object) = schema.downcast_ref::<SchemaObject>() {
+    object.get_property(key)
+  } else if let Some(array) = schema.downcast_ref::<SchemaArray>() {
+    key.parse::<usize>().ok().and_then(|_| array.items())
+  } else {
+    None
+  }
+}
+
+/// Gets the value nested directly in a value selected by a query at a key,
+/// mutably. Selecting from an array leaves out the items which were not
+/// queried, so an item is found by how many queried indexes come before it.
+fn get_child_value<'a>(value: &'a mut Value, keys: &LinearMap<Key, Query>, key: &str) -> Option<&'a mut Value> {
+  match *value {
+    Value::Object(ref mut object) => object.get_mut(key),
+    Value::Array(ref mut array) => key.parse::<usize>().ok().and_then(move |i| {
+      let position = keys.keys().filter_map(|key| key.parse::<usize>().ok()).filter(|j| *j < i).count();
+      array.get_mut(position)
+    }),
+    _ => None
+  }
+}
+
+/// Transforms a query so that references are selected as they are stored, as
+/// keys, instead of selecting properties of the referenced value.
+fn reference_keys_query(schema: &Schema, query: &Query) -> Query {
+  match *query {
+    Query::All => Query::All,
+    Query::Keys(ref keys) => {
+      let mut driver_keys = LinearMap::new();
+      for (key, sub_query) in keys.iter() {
+        let driver_query = match get_child(schema, key) {
+          Some(child) if child.downcast_ref::<SchemaReference>().is_some() => Query::All,
+          Some(child) => reference_keys_query(child, sub_query),
+          None => sub_query.clone()
+        };
+        driver_keys.insert(key.to_owned(), driver_query);
+      }
+      Query::Keys(driver_keys)
+    }
+  }
+}
+
+/// Replaces the keys of every reference selected into by the query with the
+/// values they reference. The values all have the same schema, so references
+/// at the same place in every value are loaded together.
+fn splice_references(
+  driver: &Driver,
+  definition: &Definition,
+  schema: &Schema,
+  query: &Query,
+  mut values: Vec<&mut Value>
+) -> Result<(), Error> {
+  let keys = match *query {
+    Query::All => return Ok(()),
+    Query::Keys(ref keys) => keys
+  };
+
+  for (key, sub_query) in keys.iter() {
+    let child = match get_child(schema, key) {
+      Some(child) => child,
+      None => continue
+    };
+    let children: Vec<&mut Value> = values.iter_mut().filter_map(|value| get_child_value(value, keys, key)).collect();
+    if let Some(reference) = child.downcast_ref::<SchemaReference>() {
+      if let Query::Keys(_) = *sub_query {
+        try!(splice_reference(driver, definition, reference, sub_query, children));
+      }
+    } else {
+      try!(splice_references(driver, definition, child, sub_query, children));
+    }
+  }
+
+  Ok(())
+}
+
+/// Reads every value referenced by some keys with one read, and replaces the
+/// keys with the referenced values selected with the query. Keys which do not
+/// reference a value are replaced with `null`.
+fn splice_reference(
+  driver: &Driver,
+  definition: &Definition,
+  reference: &SchemaReference,
+  query: &Query,
+  keys: Vec<&mut Value>
+) -> Result<(), Error> {
+  let type_ = try!(definition.get_type(&reference.name().to_owned()).ok_or_else(|| {
+    Error::internal(format!("Referenced type '{}' is not defined.", reference.name()))
+  }));
+
+  let mut unique_keys: Vec<Value> = Vec::new();
+  for key in &keys {
+    if **key != Value::Null && !unique_keys.iter().any(|unique_key| is_equal(unique_key, key)) {
+      unique_keys.push((**key).clone());
+    }
+  }
+  if unique_keys.is_empty() {
+    return Ok(());
+  }
+
+  // The key property is always read so that values can be matched with the
+  // keys which reference them.
+  let read_query = match *query {
+    Query::Keys(ref properties) if !properties.contains_key(type_.key()) => {
+      let mut properties = properties.clone();
+      properties.insert(type_.key().to_owned(), Query::All);
+      Query::Keys(properties)
+    },
+    _ => query.clone()
+  };
+
+  let referenced: Vec<Value> = try!(read_related(
+    driver,
+    definition,
+    type_,
+    Condition::Keys(linear_map! { type_.key().to_owned() => Condition::In(unique_keys) }),
+    Default::default(),
+    Default::default(),
+    read_query
+  )).collect();
+
+  for key in keys {
+    let value = referenced.iter()
+      .find(|value| value.get(vec![type_.key().to_owned()]).map_or(false, |value_key| is_equal(value_key, key)))
+      .and_then(|value| query.select(value))
+      .unwrap_or(Value::Null);
+    *key = value;
+  }
+
+  Ok(())
+}
+
+/// Compares keys the same way `Condition::In` does.
+fn is_equal(a: &Value, b: &Value) -> bool {
+  a.compare(b) == Some(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::Cell;
+
+  use driver::Driver;
+  use driver::memory::MemoryDriver;
+  use driver::relation::read_related;
+  use error::Error;
+  use query::{Condition, SortRule, Range, Query};
+  use schema::{Definition, Type, Schema};
+  use value::{Value, ValueIter};
+
+  /// Counts the reads made on a memory driver.
+  struct CountingDriver {
+    driver: MemoryDriver,
+    reads: Cell<u64>
+  }
+
+  impl Driver for CountingDriver {
+    fn read(&self, type_: &Type, condition: Condition, sort: Vec<SortRule>, range: Range, query: Query) -> Result<ValueIter, Error> {
+      self.reads.set(self.reads.get() + 1);
+      self.driver.read(type_, condition, sort, range, query)
+    }
+
+    fn create(&self, type_: &Type, values: Vec<Value>) -> Result<Vec<Value>, Error> { self.driver.create(type_, values) }
+    fn update(&self, type_: &Type, condition: Condition, patch: Value) -> Result<u64, Error> { self.driver.update(type_, condition, patch) }
+    fn delete(&self, type_: &Type, condition: Condition) -> Result<u64, Error> { self.driver.delete(type_, condition) }
+  }
+
+  fn get_fixtures() -> (Definition, CountingDriver) {
+    let mut definition = Definition::new();
+    definition.add_type("person", {
+      let mut person = Type::new();
+      person.add_property("id", Schema::string());
+      person.add_property("name", Schema::string());
+      person.add_property("mentor", Schema::reference("mentor"));
+      person
+    });
+    definition.add_type("mentor", {
+      let mut mentor = Type::new();
+      mentor.add_property("id", Schema::number());
+      mentor.add_property("name", Schema::string());
+      mentor
+    });
+    definition.add_type("post", {
+      let mut post = Type::new();
+      post.add_property("headline", Schema::string());
+      post.add_property("author", Schema::reference("person"));
+      post.add_property("editors", {
+        let mut editors = Schema::array();
+        editors.set_items(Schema::reference("person"));
+        editors
+      });
+      post
+    });
+    definition.resolve_references().unwrap();
+    let driver = CountingDriver { driver: MemoryDriver::new(), reads: Cell::new(0) };
+    driver.create(definition.get_type(&str!("mentor")).unwrap(), vec![
+      value!({ "id" => 1, "name" => "Grace" })
+    ]).unwrap();
+    driver.create(definition.get_type(&str!("person")).unwrap(), vec![
+      value!({ "id" => "ada", "name" => "Ada", "mentor" => 1 }),
+      value!({ "id" => "alan", "name" => "Alan" })
+    ]).unwrap();
+    driver.create(definition.get_type(&str!("post")).unwrap(), vec![
+      value!({ "headline" => "Engines", "author" => "ada", "editors" => ["alan", "ada"] }),
+      value!({ "headline" => "Machines", "author" => "alan" }),
+      value!({ "headline" => "Ghosts", "author" => "nobody" })
+    ]).unwrap();
+    (definition, driver)
+  }
+
+  fn read(definition: &Definition, driver: &CountingDriver, query: Query) -> Vec<Value> {
+    let post = definition.get_type(&str!("post")).unwrap();
+    read_related(driver, definition, post, Default::default(), vec![], Default::default(), query).unwrap().collect()
+  }
+
+  #[test]
+  fn test_read_keys() {
+    let (definition, driver) = get_fixtures();
+    assert_eq!(read(&definition, &driver, Query::Keys(linear_map! {
+      str!("author") => Query::All
+    })), vec![value!({ "author" => "ada" }), value!({ "author" => "alan" }), value!({ "author" => "nobody" })]);
+    assert_eq!(driver.reads.get(), 1);
+  }
+
+  #[test]
+  fn test_read_references() {
+    let (definition, driver) = get_fixtures();
+    assert_eq!(read(&definition, &driver, Query::Keys(linear_map! {
+      str!("headline") => Query::All,
+      str!("author") => Query::Keys(linear_map! {
+        str!("name") => Query::All,
+        str!("mentor") => Query::Keys(linear_map! {
+          str!("name") => Query::All
+        })
+      })
+    })), vec![
+      value!({ "headline" => "Engines", "author" => { "name" => "Ada", "mentor" => { "name" => "Grace" } } }),
+      value!({ "headline" => "Machines", "author" => { "name" => "Alan" } }),
+      value!({ "headline" => "Ghosts", "author" => () })
+    ]);
+    // One read for posts, one for every author, and one for every mentor.
+    assert_eq!(driver.reads.get(), 3);
+  }
+
+  #[test]
+  fn test_read_array_references() {
+    let (definition, driver) = get_fixtures();
+    assert_eq!(read(&definition, &driver, Query::Keys(linear_map! {
+      str!("editors") => Query::Keys(linear_map! {
+        str!("0") => Query::Keys(linear_map! { str!("name") => Query::All }),
+        str!("1") => Query::Keys(linear_map! { str!("id") => Query::All })
+      })
+    })), vec![
+      value!({ "editors" => [{ "name" => "Alan" }, { "id" => "ada" }] }),
+      value!({}),
+      value!({})
+    ]);
+    assert_eq!(read(&definition, &driver, Query::Keys(linear_map! {
+      str!("editors") => Query::Keys(linear_map! {
+        str!("1") => Query::Keys(linear_map! { str!("name") => Query::All })
+      })
+    }))[0], value!({ "editors" => [{ "name" => "Ada" }] }));
+  }
+}
@@ -0,0 +1,195 @@
+//! Routes every driver call for a type to the driver configured for that
+//! type, so that different types may be stored in different data sources.
+
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use driver::Driver;
+use error::{Error, NotFound};
+use query::{Condition, SortRule, Range, Query, Cursor, Aggregation};
+use schema::Type;
+use value::{Key, Value, ValueIter};
+
+/// A driver which holds other drivers by type name and sends every call to
+/// the driver for the name of the type being called with. A single driver
+/// may be routed to from many types.
+pub struct DriverRouter {
+  drivers: BTreeMap<Key, Rc<Driver>>
+}
+
+impl DriverRouter {
+  /// Creates a new router without any routes.
+  pub fn new() -> Self {
+    DriverRouter {
+      drivers: BTreeMap::new()
+    }
+  }
+
+  /// Routes a type name to a driver.
+  pub fn add_driver<K, D>(&mut self, name: K, driver: D) where K: Into<Key>, D: Driver + 'static {
+    self.drivers.insert(name.into(), Rc::new(driver));
+  }
+
+  /// Routes a type name to a driver which may also be routed to by other
+  /// type names.
+  pub fn add_shared_driver<K>(&mut self, name: K, driver: Rc<Driver>) where K: Into<Key> {
+    self.drivers.insert(name.into(), driver);
+  }
+
+  /// Gets the driver a type name is routed to.
+  pub fn get_driver(&self, name: &str) -> Option<&Driver> {
+    self.drivers.get(name).map(|driver| &**driver)
+  }
+
+  /// Gets the type names which have a route, in order.
+  pub fn names(&self) -> Vec<&str> {
+    self.drivers.keys().map(String::as_str).collect()
+  }
+
+  /// Gets the driver for a type, or a `NotFound` error if the type has no
+  /// route.
+  fn route(&self, type_: &Type) -> Result<&Driver, Error> {
+    self.get_driver(type_.name()).ok_or_else(|| {
+      Error::new(NotFound, format!("No driver is routed for type '{}'.", type_.name()))
+        .set_hint("Try configuring a driver for the type in the definition.")
+    })
+  }
+}
+
+impl Driver for DriverRouter {
+  fn read(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    range: Range,
+    query: Query
+  ) -> Result<ValueIter, Error> {
+    try!(self.route(type_)).read(type_, condition, sort, range, query)
+  }
+
+  fn create(
+    &self,
+    type_: &Type,
+    values: Vec<Value>
+  ) -> Result<Vec<Value>, Error> {
+    try!(self.route(type_)).create(type_, values)
+  }
+
+  fn update(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    patch: Value
+  ) -> Result<u64, Error> {
+    try!(self.route(type_)).update(type_, condition, patch)
+  }
+
+  fn delete(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    try!(self.route(type_)).delete(type_, condition)
+  }
+
+  // The methods below have default implementations, however they are routed
+  // so that the optimizations of the routed driver are used.
+
+  fn count(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    try!(self.route(type_)).count(type_, condition)
+  }
+
+  fn aggregate(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    aggregation: Aggregation
+  ) -> Result<ValueIter, Error> {
+    try!(self.route(type_)).aggregate(type_, condition, aggregation)
+  }
+
+  fn read_with_total(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    range: Range,
+    query: Query
+  ) -> Result<(ValueIter, u64), Error> {
+    try!(self.route(type_)).read_with_total(type_, condition, sort, range, query)
+  }
+
+  fn read_one(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    query: Query
+  ) -> Result<Value, Error> {
+    try!(self.route(type_)).read_one(type_, condition, query)
+  }
+
+  fn read_page(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    limit: u64,
+    after: Option<Cursor>,
+    query: Query
+  ) -> Result<(ValueIter, Option<Cursor>), Error> {
+    try!(self.route(type_)).read_page(type_, condition, sort, limit, after, query)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::rc::Rc;
+
+  use driver::{Driver, DriverRouter};
+  use driver::memory::MemoryDriver;
+  use error::NotFound;
+  use query::Condition;
+  use schema::Type;
+
+  fn get_type(name: &str) -> Type {
+    let mut type_ = Type::new();
+    type_.set_name(name);
+    type_
+  }
+
+  #[test]
+  fn test_route() {
+    let shared: Rc<Driver> = Rc::new(MemoryDriver::new());
+    let mut router = DriverRouter::new();
+    router.add_shared_driver("a", shared.clone());
+    router.add_shared_driver("b", shared.clone());
+    router.add_driver("c", MemoryDriver::new());
+    assert_eq!(router.names(), vec!["a", "b", "c"]);
+    router.create(&get_type("a"), vec![value!({ "hello" => "world" })]).unwrap();
+    router.create(&get_type("c"), vec![value!({ "hello" => "moon" }), value!({ "hello" => "sun" })]).unwrap();
+    assert_eq!(router.read(&get_type("a"), Default::default(), vec![], Default::default(), Default::default()).unwrap().collect::<Vec<_>>(), vec![value!({ "hello" => "world" })]);
+    assert_eq!(shared.count(&get_type("a"), Condition::True).unwrap(), 1);
+    assert_eq!(router.get_driver("c").unwrap().count(&get_type("c"), Condition::True).unwrap(), 2);
+    assert_eq!(router.count(&get_type("b"), Condition::True).unwrap(), 0);
+  }
+
+  #[test]
+  fn test_route_unknown() {
+    let router = DriverRouter::new();
+    let error = router.read(&get_type("a"), Default::default(), vec![], Default::default(), Default::default()).err().unwrap();
+    assert_eq!(error.code(), &NotFound);
+    error.expect("No driver is routed for type 'a'.");
+  }
+
+  #[test]
+  fn test_boxed_drivers() {
+    let drivers: Vec<Box<Driver>> = vec![Box::new(MemoryDriver::new()), Box::new(DriverRouter::new())];
+    assert_eq!(drivers[0].count(&get_type("a"), Condition::True).unwrap(), 0);
+    assert!(drivers[1].count(&get_type("a"), Condition::True).is_err());
+  }
+}
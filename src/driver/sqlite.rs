@@ -0,0 +1,385 @@
+//! A driver for [SQLite][1] databases. Every type maps to a table of the same
+//! name with a column for every top level property. Booleans are stored as
+//! integers, and objects or arrays are stored as JSON text. The schema of the
+//! type is used to turn these columns back into values.
+//!
+//! SQL is generated with `query::sql` in the SQLite dialect. Nested
+//! properties are accessed with the [JSON1 extension][2] and patterns are
+//! matched with a `regexp` function which is added on connection.
+//!
+//! SQL `NULL` columns are left out of read values, so a property set to
+//! `null` is read as a missing property.
+//!
+//! [1]: https://www.sqlite.org
+//! [2]: https://www.sqlite.org/json1.html
+
+use std::error::Error as ErrorTrait;
+
+use linear_map::LinearMap;
+use regex::Regex;
+use rusqlite::{Connection, Row, Error as SqliteError};
+use rusqlite::types::{ToSql, Value as SqlValue};
+use url::Url;
+
+use driver::{Driver, Connect};
+use error::Error;
+use query::{Range, SortRule, Condition, Query};
+use query::sql::{Dialect, Statement};
+use schema::{Type, Schema, SchemaBoolean, SchemaNumber, SchemaArray, SchemaObject};
+use value::{Key, Value, ValueIter};
+
+/// A driver connected to a single SQLite database.
+pub struct SqliteDriver {
+  connection: Connection
+}
+
+impl SqliteDriver {
+  /// Executes a statement and returns how many rows were changed.
+  fn execute(&self, statement: Statement) -> Result<u64, Error> {
+    let (sql, params) = statement.into_parts();
+    let params = params.into_iter().map(value_to_sql).collect::<Vec<_>>();
+    Ok(try!(self.connection.execute(&sql, &to_sql_params(&params))) as u64)
+  }
+}
+
+impl Connect for SqliteDriver {
+  /// Connects to a URL like `sqlite:path/to/data.db`, `sqlite:///data.db`
+  /// for an absolute path, or `sqlite::memory:` for an in-memory database.
+  fn connect(url: &Url) -> Result<Self, Error> {
+    let path = match (url.scheme.as_str(), url.non_relative_scheme_data()) {
+      ("sqlite", Some(path)) if !path.is_empty() => path.trim_left_matches("//"),
+      _ => return Err(Error::invalid(
+        format!("Cannot connect to '{}' with the SQLite driver.", url),
+        "Try a URL like `sqlite:path/to/data.db` or `sqlite::memory:`."
+      ))
+    };
+
+    let connection = try!(if path == ":memory:" {
+      Connection::open_in_memory()
+    } else {
+      Connection::open(path)
+    });
+
+    // SQLite parses `a REGEXP b` but does not define the function it calls,
+    // `regexp(b, a)`. Values which are not text never match.
+    try!(connection.create_scalar_function("regexp", 2, true, |context| {
+      let pattern = try!(context.get::<String>(0));
+      let regex = try!(Regex::new(&pattern).map_err(|error| SqliteError::UserFunctionError(Box::new(error))));
+      Ok(match try!(context.get::<SqlValue>(1)) {
+        SqlValue::Text(text) => regex.is_match(&text),
+        _ => false
+      })
+    }));
+
+    Ok(SqliteDriver {
+      connection: connection
+    })
+  }
+}
+
+impl Driver for SqliteDriver {
+  fn read(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    range: Range,
+    query: Query
+  ) -> Result<ValueIter, Error> {
+    let (sql, params) = try!(Dialect::Sqlite.select(type_.name(), condition, sort, range, &query)).into_parts();
+    let params = params.into_iter().map(value_to_sql).collect::<Vec<_>>();
+
+    let properties = type_.properties();
+    let mut statement = try!(self.connection.prepare(&sql));
+    let column_names = statement.column_names().into_iter().map(str::to_owned).collect::<Vec<_>>();
+    let rows = try!(statement.query_map(&to_sql_params(&params), |row| row_to_value(&properties, &column_names, row)));
+
+    let mut values = Vec::new();
+    for row in rows {
+      if let Some(value) = query.select(&try!(try!(row))) {
+        values.push(value);
+      }
+    }
+
+    Ok(ValueIter::new(values.into_iter()))
+  }
+
+  fn count(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    let (sql, params) = try!(Dialect::Sqlite.count(type_.name(), condition)).into_parts();
+    let params = params.into_iter().map(value_to_sql).collect::<Vec<_>>();
+    let count: i64 = try!(self.connection.query_row(&sql, &to_sql_params(&params), |row| row.get(0)));
+    Ok(count as u64)
+  }
+
+  fn create(
+    &self,
+    type_: &Type,
+    values: Vec<Value>
+  ) -> Result<Vec<Value>, Error> {
+    let mut objects = Vec::new();
+
+    for value in &values {
+      match *value {
+        Value::Object(ref object) => objects.push(object.clone()),
+        _ => return Err(Error::invalid(
+          format!("Cannot create a non-object value in type '{}'.", type_.name()),
+          "Try creating an object value instead."
+        ))
+      }
+    }
+
+    // Either every object is inserted or none are. `Connection::transaction`
+    // needs a mutable connection, so the transaction is started and ended
+    // with SQL instead.
+    try!(self.connection.execute_batch("BEGIN"));
+    for object in objects {
+      if let Err(error) = self.execute(Dialect::Sqlite.insert(type_.name(), object)) {
+        try!(self.connection.execute_batch("ROLLBACK"));
+        return Err(error);
+      }
+    }
+    try!(self.connection.execute_batch("COMMIT"));
+
+    Ok(values)
+  }
+
+  fn update(
+    &self,
+    type_: &Type,
+    condition: Condition,
+    patch: Value
+  ) -> Result<u64, Error> {
+    let patch = match patch {
+      Value::Object(patch) => patch,
+      _ => return Err(Error::invalid(
+        format!("Cannot update values in type '{}' with a non-object patch.", type_.name()),
+        "Try using an object patch instead."
+      ))
+    };
+
+    // An empty `SET` is not valid SQL, but an empty patch still updates every
+    // value which passes the condition.
+    if patch.is_empty() {
+      return self.count(type_, condition);
+    }
+
+    self.execute(try!(Dialect::Sqlite.update(type_.name(), condition, patch)))
+  }
+
+  fn delete(
+    &self,
+    type_: &Type,
+    condition: Condition
+  ) -> Result<u64, Error> {
+    self.execute(try!(Dialect::Sqlite.delete(type_.name(), condition)))
+  }
+}
+
+impl From<SqliteError> for Error {
+  fn from(error: SqliteError) -> Self {
+    Error::internal(error.description())
+  }
+}
+
+/// Transforms a value into a SQLite value. Booleans become integers and
+/// objects or arrays become JSON text.
+fn value_to_sql(value: Value) -> SqlValue {
+  match value {
+    Value::Null => SqlValue::Null,
+    Value::Boolean(value) => SqlValue::Integer(if value { 1 } else { 0 }),
+    Value::I64(value) => SqlValue::Integer(value),
+    Value::F64(value) => SqlValue::Real(value),
+    Value::String(value) => SqlValue::Text(value),
+    value @ Value::Object(_) | value @ Value::Array(_) => SqlValue::Text(value.to_json().unwrap_or_else(|_| "null".to_owned()))
+  }
+}
+
+/// Borrows parameters in the form `rusqlite` expects.
+fn to_sql_params(params: &[SqlValue]) -> Vec<&ToSql> {
+  params.iter().map(|param| param as &ToSql).collect()
+}
+
+/// Transforms a row into a value using the schema of every column to decode
+/// booleans, integers, objects, and arrays. `NULL` columns are left out.
+fn row_to_value(properties: &LinearMap<Key, &Schema>, columns: &[String], row: &Row) -> Result<Value, Error> {
+  let mut object = LinearMap::new();
+
+  for (i, column) in columns.iter().enumerate() {
+    let (is_boolean, is_integer, is_json) = properties.get(column).map_or((false, false, false), |schema| (
+      schema.downcast_ref::<SchemaBoolean>().is_some(),
+      schema.downcast_ref::<SchemaNumber>().map_or(false, SchemaNumber::integer),
+      schema.downcast_ref::<SchemaObject>().is_some() || schema.downcast_ref::<SchemaArray>().is_some()
+    ));
+    let value = match try!(row.get_checked::<SqlValue>(i as i32)) {
+      SqlValue::Null => continue,
+      SqlValue::Integer(value) => {
+        if is_boolean {
+          Value::Boolean(value != 0)
+        } else {
+          Value::I64(value)
+        }
+      },
+      SqlValue::Real(value) => {
+        if is_integer && value.fract() == 0.0 {
+          Value::I64(value as i64)
+        } else {
+          Value::F64(value)
+        }
+      },
+      SqlValue::Text(value) => {
+        if is_json {
+          try!(Value::from_json(&value))
+        } else {
+          Value::String(value)
+        }
+      },
+      SqlValue::Blob(_) => continue
+    };
+    object.insert(column.to_owned(), value);
+  }
+
+  Ok(Value::Object(object))
+}
+
+#[cfg(test)]
+mod tests {
+  use regex::Regex;
+  use url::Url;
+
+  use driver::{Driver, Connect};
+  use driver::sqlite::SqliteDriver;
+  use driver::testing::read;
+  use query::{Range, SortRule, Condition, Query};
+  use schema::{Type, Schema, DriverConfig};
+  use value::Value;
+
+  fn val_a() -> Value { value!({ "a" => 1, "b" => 2, "c" => 3, "d" => 4 }) }
+  fn val_b() -> Value { value!({ "b" => 2, "c" => 4, "hello" => "world", "doc_a" => { "a" => 1, "b" => 2, "c" => 3, "d" => 4 } }) }
+  fn val_c() -> Value { value!({ "a" => 1, "c" => 3, "doc_b" => { "b" => 2, "c" => 4, "hello" => "world", "doc_a" => { "a" => 1, "b" => 2, "c" => 3, "d" => 4 } }, "tags" => ["x", "y"], "ok" => true }) }
+
+  fn get_fixtures() -> (Type, SqliteDriver) {
+    let mut type_ = Type::new();
+    type_.set_name("test");
+    type_.add_property("doc_a", Schema::object());
+    type_.add_property("doc_b", Schema::object());
+    type_.add_property("tags", Schema::array());
+    type_.add_property("ok", Schema::boolean());
+    type_.set_driver(DriverConfig::new(Url::parse("sqlite::memory:").unwrap()));
+    let driver = SqliteDriver::connect(type_.driver().unwrap().url()).unwrap();
+    driver.connection.execute_batch("CREATE TABLE test (a, b, c, d, hello, doc_a, doc_b, tags, ok)").unwrap();
+    driver.create(&type_, vec![val_a(), val_b(), val_c()]).unwrap();
+    (type_, driver)
+  }
+
+  #[test]
+  fn test_connect() {
+    assert!(SqliteDriver::connect(&Url::parse("sqlite::memory:").unwrap()).is_ok());
+    SqliteDriver::connect(&Url::parse("mongodb://localhost/test").unwrap()).err().unwrap().expect("Cannot connect");
+  }
+
+  #[test]
+  fn test_read_all() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Default::default(), Default::default()), vec![val_a(), val_b(), val_c()]);
+  }
+
+  #[test]
+  fn test_read_condition() {
+    let (type_, driver) = get_fixtures();
+    let read_condition = |condition| read(&driver, &type_, condition, vec![], Default::default(), Default::default());
+    assert_eq!(read_condition(Condition::False), vec![]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("c") => Condition::Equal(Value::I64(3)) })), vec![val_a(), val_c()]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("a") => Condition::Equal(Value::Null) })), vec![val_b()]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("a") => Condition::Not(Box::new(Condition::Equal(Value::I64(1)))) })), vec![val_b()]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("c") => Condition::GreaterThanOrEqual(Value::F64(3.5)) })), vec![val_b()]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("hello") => Condition::LessThan(Value::I64(5)) })), vec![]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("c") => Condition::In(vec![Value::I64(4), Value::I64(5)]) })), vec![val_b()]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("hello") => Condition::NotIn(vec![Value::String(str!("world"))]) })), vec![val_a(), val_c()]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("hello") => Condition::Matches(Regex::new("^wor").unwrap()) })), vec![val_b()]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("tags") => Condition::Contains(Value::String(str!("y"))) })), vec![val_c()]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("hello") => Condition::Contains(Value::String(str!("world"))) })), vec![]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("ok") => Condition::Equal(Value::Boolean(true)) })), vec![val_c()]);
+    assert_eq!(read_condition(Condition::Keys(linear_map! { str!("doc_a") => Condition::Keys(linear_map! { str!("d") => Condition::Exists }) })), vec![val_b()]);
+    assert_eq!(
+      read_condition(Condition::Keys(linear_map! {
+        str!("doc_b") => Condition::Keys(linear_map! {
+          str!("doc_a") => Condition::Keys(linear_map! {
+            str!("d") => Condition::Equal(Value::I64(4))
+          })
+        })
+      })),
+      vec![val_c()]
+    );
+  }
+
+  #[test]
+  fn test_read_sort() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(read(&driver, &type_, Default::default(), vec![SortRule::new(point!["c"], false), SortRule::new(point!["d"], true)], Default::default(), Default::default()), vec![val_b(), val_c(), val_a()]);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![SortRule::new(point!["doc_a", "c"], false)], Default::default(), Default::default())[0], val_b());
+  }
+
+  #[test]
+  fn test_read_range() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Range::new(None, Some(2)), Default::default()), vec![val_a(), val_b()]);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Range::new(Some(1), Some(1)), Default::default()), vec![val_b()]);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Range::new(Some(1), None), Default::default()), vec![val_b(), val_c()]);
+  }
+
+  #[test]
+  fn test_read_query() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(
+      read(&driver, &type_, Default::default(), vec![], Default::default(), Query::Keys(linear_map! {
+        str!("a") => Query::All,
+        str!("doc_a") => Query::Keys(linear_map! {
+          str!("b") => Query::All
+        })
+      })),
+      vec![value!({ "a" => 1 }), value!({ "doc_a" => { "b" => 2 } }), value!({ "a" => 1 })]
+    );
+  }
+
+  #[test]
+  fn test_count() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(driver.count(&type_, Condition::True).unwrap(), 3);
+    assert_eq!(driver.count(&type_, Condition::Keys(linear_map! { str!("c") => Condition::Equal(Value::I64(3)) })).unwrap(), 2);
+  }
+
+  #[test]
+  fn test_create() {
+    let (type_, driver) = get_fixtures();
+    driver.create(&type_, vec![value!({}), value!({ "a" => 5, "tags" => [] })]).unwrap();
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Range::new(Some(3), None), Default::default()), vec![value!({}), value!({ "a" => 5, "tags" => [] })]);
+    driver.create(&type_, vec![value!({ "a" => 6 }), value!(7)]).unwrap_err().expect("non-object value");
+    assert_eq!(driver.count(&type_, Condition::True).unwrap(), 5);
+    // A failed insert rolls back the objects inserted before it.
+    assert!(driver.create(&type_, vec![value!({ "a" => 6 }), value!({ "missing" => 7 })]).is_err());
+    assert_eq!(driver.count(&type_, Condition::True).unwrap(), 5);
+    driver.create(&type_, vec![value!({ "a" => 6 })]).unwrap();
+    assert_eq!(driver.count(&type_, Condition::True).unwrap(), 6);
+  }
+
+  #[test]
+  fn test_update() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(driver.update(&type_, Condition::Keys(linear_map! { str!("c") => Condition::Equal(Value::I64(3)) }), value!({ "c" => 5, "hello" => "moon" })).unwrap(), 2);
+    assert_eq!(driver.count(&type_, Condition::Keys(linear_map! { str!("hello") => Condition::Equal(Value::String(str!("moon"))) })).unwrap(), 2);
+    assert_eq!(driver.update(&type_, Condition::True, value!({})).unwrap(), 3);
+    driver.update(&type_, Condition::True, value!(5)).unwrap_err().expect("non-object patch");
+  }
+
+  #[test]
+  fn test_delete() {
+    let (type_, driver) = get_fixtures();
+    assert_eq!(driver.delete(&type_, Condition::Keys(linear_map! { str!("c") => Condition::Equal(Value::I64(3)) })).unwrap(), 2);
+    assert_eq!(read(&driver, &type_, Default::default(), vec![], Default::default(), Default::default()), vec![val_b()]);
+    assert_eq!(driver.delete(&type_, Condition::False).unwrap(), 0);
+  }
+}
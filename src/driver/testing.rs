@@ -0,0 +1,25 @@
+//! Helpers shared by the tests of every driver.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use driver::Driver;
+use query::{Range, SortRule, Condition, Query};
+use schema::Type;
+use value::Value;
+
+/// Reads some values from a driver, panicking on an error.
+pub fn read<D>(driver: &D, type_: &Type, condition: Condition, sort: Vec<SortRule>, range: Range, query: Query) -> Vec<Value> where D: Driver {
+  driver.read(type_, condition, sort, range, query).unwrap().collect()
+}
+
+/// Creates an empty temporary directory for a test of a driver. The name has
+/// the process id, so that concurrent test runs never share a directory.
+pub fn temp_directory(driver: &str, test: &str) -> PathBuf {
+  let directory = env::temp_dir().join(format!("ardite-{}-driver-{}-{}", driver, process::id(), test));
+  let _ = fs::remove_dir_all(&directory);
+  fs::create_dir_all(&directory).unwrap();
+  directory
+}
@@ -10,6 +10,7 @@ use regex::Regex;
 use serde_json::error::Error as JSONError;
 use serde_yaml::error::Error as YAMLError;
 
+use schema::Failure;
 use value::{Object, Value};
 
 /// Any error generated by Ardite or it‘s drivers should be output using this
@@ -23,6 +24,9 @@ use value::{Object, Value};
 /// Typically hints should be included for what would be considered the `4xx`
 /// (in HTTP language) class of error codes.
 ///
+/// Errors created from a validation report also include a list of every
+/// failure found during validation.
+///
 /// # Tips For Writing Good Hint Messages
 /// - Write in the second person (“You should…”).
 /// - Always recommend a solution (“You should try…”, not “You must do…”).
@@ -39,7 +43,10 @@ pub struct Error {
   message: String,
   /// A hint to the user on what to do next to try and avoid the error
   /// happening again. This is optional.
-  hint: Option<String>
+  hint: Option<String>,
+  /// Every failure found when validating against a schema. Empty for errors
+  /// which were not created from a validation report.
+  failures: Vec<Failure>
 }
 
 impl Error {
@@ -48,7 +55,8 @@ impl Error {
     Error {
       code: code,
       message: message.into(),
-      hint: None
+      hint: None,
+      failures: Vec::new()
     }
   }
 
@@ -58,6 +66,12 @@ impl Error {
     self
   }
 
+  /// Sets the validation failures in a chainable fashion.
+  pub fn set_failures(mut self, failures: Vec<Failure>) -> Self {
+    self.failures = failures;
+    self
+  }
+
   /// Get the code for the error.
   pub fn code(&self) -> &ErrorCode {
     &self.code
@@ -73,7 +87,13 @@ impl Error {
     self.hint.as_ref().map(|s| s.as_str())
   }
 
-  /// Gets an object which represents the error.
+  /// Get the validation failures which caused the error.
+  pub fn failures(&self) -> &Vec<Failure> {
+    &self.failures
+  }
+
+  /// Gets an object which represents the error. If the error has validation
+  /// failures they are included as a list under the `errors` key.
   ///
   /// # Example
   /// ```rust
@@ -107,6 +127,10 @@ impl Error {
       object.insert("hint".to_owned(), Value::String(hint.clone()));
     }
 
+    if !self.failures.is_empty() {
+      object.insert("errors".to_owned(), Value::Array(self.failures.iter().map(Failure::to_value).collect()));
+    }
+
     Value::Object(object)
   }
 
@@ -125,7 +149,8 @@ impl Error {
     Error {
       code: ErrorCode::BadRequest,
       message: message.into(),
-      hint: Some(hint.into())
+      hint: Some(hint.into()),
+      failures: Vec::new()
     }
   }
 
@@ -144,7 +169,8 @@ impl Error {
     Error {
       code: ErrorCode::Internal,
       message: message.into(),
-      hint: None
+      hint: None,
+      failures: Vec::new()
     }
   }
 
@@ -164,7 +190,8 @@ impl Error {
     Error {
       code: ErrorCode::NotImplemented,
       message: message.into(),
-      hint: None
+      hint: None,
+      failures: Vec::new()
     }
   }
 
@@ -189,6 +216,10 @@ impl Display for Error {
       try!(write!(fmt, "\nhint: {}", hint));
     }
 
+    for failure in &self.failures {
+      try!(write!(fmt, "\nfailure: \"{}\" ({}) {}", failure.pointer_string(), failure.keyword(), failure.error().message()));
+    }
+
     Ok(())
   }
 }
@@ -204,7 +235,8 @@ impl From<IOError> for Error {
     Error {
       code: ErrorCode::Internal,
       message: error.description().to_owned(),
-      hint: None
+      hint: None,
+      failures: Vec::new()
     }
   }
 }
@@ -216,14 +248,16 @@ impl From<JSONError> for Error {
         Error {
           code: ErrorCode::BadRequest,
           message: format!("{}", error),
-          hint: Some(format!("Make sure your JSON syntax is correct around line {} column {}.", line, column))
+          hint: Some(format!("Make sure your JSON syntax is correct around line {} column {}.", line, column)),
+          failures: Vec::new()
         }
       },
       _ => {
         Error {
           code: ErrorCode::Internal,
           message: error.description().to_owned(),
-          hint: None
+          hint: None,
+          failures: Vec::new()
         }
       }
     }
@@ -237,14 +271,16 @@ impl From<YAMLError> for Error {
         Error {
           code: ErrorCode::BadRequest,
           message: message.to_owned(),
-          hint: Some("Make sure your YAML syntax is correct.".to_owned())
+          hint: Some("Make sure your YAML syntax is correct.".to_owned()),
+          failures: Vec::new()
         }
       },
       _ => {
         Error {
           code: ErrorCode::Internal,
           message: error.description().to_owned(),
-          hint: None
+          hint: None,
+          failures: Vec::new()
         }
       }
     }
@@ -1,6 +1,7 @@
 #![allow(unknown_lints)]
 // TODO: #![deny(missing_docs)]
 
+extern crate chrono;
 #[macro_use(lazy_static)]
 extern crate lazy_static;
 #[macro_use(linear_map)]
@@ -10,12 +11,17 @@ extern crate serde;
 extern crate serde_json;
 extern crate serde_yaml;
 extern crate url;
+extern crate uuid;
 
 #[cfg(feature = "driver_mongodb")]
 #[macro_use(bson, doc)]
 extern crate bson;
 #[cfg(feature = "driver_mongodb")]
 extern crate mongodb;
+#[cfg(feature = "driver_sqlite")]
+extern crate rusqlite;
+#[cfg(feature = "driver_csv")]
+extern crate csv;
 
 #[macro_use]
 mod macros;
@@ -24,9 +30,11 @@ pub mod driver;
 pub mod error;
 pub mod query;
 pub mod schema;
+pub mod service;
 pub mod value;
 
-pub use driver::Driver;
+pub use driver::{Driver, Connect};
 pub use error::Error;
 pub use schema::{Definition, Type, DriverConfig, Schema};
+pub use service::Service;
 pub use value::{Key, Pointer, Object, Array, Value};
@@ -0,0 +1,289 @@
+//! Aggregations which group values and summarize every group.
+
+use std::cmp::Ordering;
+
+use linear_map::LinearMap;
+
+use error::Error;
+use query::SortRule;
+use value::{Key, Pointer, Object, Value};
+
+/// Groups values by the values of some properties, and then computes
+/// accumulators like a count or a sum for every group. Similar to a SQL
+/// `GROUP BY` clause or the MongoDB `$group` pipeline stage.
+///
+/// Every group is returned as an object. The object has the grouped values
+/// at the same pointers they were at in the original values, and the result
+/// of every accumulator under the accumulator’s name. For example grouping by
+/// `/address/city` with a `count` accumulator returns objects like
+/// `{ "address": { "city": "Paris" }, "count": 3 }`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Aggregation {
+  /// The properties to group values by. Values with equal properties are in
+  /// the same group. Missing properties are grouped as `null`.
+  group_by: Vec<Pointer>,
+  /// The accumulators to compute for every group by name.
+  accumulators: LinearMap<Key, Accumulator>
+}
+
+/// Summarizes all of the values in a group into a single value.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Accumulator {
+  /// The number of values in the group.
+  Count,
+  /// The sum of a numeric property. Non-numeric properties are ignored. If
+  /// any of the numbers is a float the sum is a float.
+  Sum(Pointer),
+  /// The smallest value of a property using the `SortRule` order. Missing and
+  /// `null` properties are ignored. `null` if no value has the property.
+  Min(Pointer),
+  /// The largest value of a property using the `SortRule` order. Missing and
+  /// `null` properties are ignored. `null` if no value has the property.
+  Max(Pointer),
+  /// The average of a numeric property as a float. Non-numeric properties are
+  /// ignored. `null` if no value has a numeric property.
+  Average(Pointer)
+}
+
+impl Aggregation {
+  /// Creates an aggregation grouping by some properties. With no properties
+  /// all values are in a single group.
+  pub fn new(group_by: Vec<Pointer>) -> Self {
+    Aggregation {
+      group_by: group_by,
+      accumulators: LinearMap::new()
+    }
+  }
+
+  /// Adds an accumulator which will be computed for every group.
+  pub fn add_accumulator<K>(&mut self, name: K, accumulator: Accumulator) where K: Into<Key> {
+    self.accumulators.insert(name.into(), accumulator);
+  }
+
+  /// Get the properties values are grouped by.
+  pub fn group_by(&self) -> &Vec<Pointer> {
+    &self.group_by
+  }
+
+  /// Get the accumulators by name.
+  pub fn accumulators(&self) -> &LinearMap<Key, Accumulator> {
+    &self.accumulators
+  }
+
+  /// Checks that no accumulator name clashes with another property of the
+  /// returned groups. An accumulator may not be named `_id`, which is
+  /// reserved by drivers like MongoDB, or share its name with the first key
+  /// of a grouped pointer as it would overwrite the grouped value.
+  pub fn check(&self) -> Result<(), Error> {
+    for name in self.accumulators.keys() {
+      if name == "_id" {
+        return Err(Error::invalid(
+          "Cannot name an accumulator `_id`.",
+          "Try using another name for the accumulator."
+        ));
+      }
+      if self.group_by.iter().any(|pointer| pointer.first() == Some(name)) {
+        return Err(Error::invalid(
+          format!("Accumulator `{}` would overwrite the grouped property `{}`.", name, name),
+          "Try using a name which is not grouped by."
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  /// Aggregates some values in memory. Groups are returned in the order
+  /// their first value was found. Drivers which can not aggregate natively
+  /// should use this function.
+  pub fn aggregate<'a, I>(&self, values: I) -> Vec<Value> where I: Iterator<Item=&'a Value> {
+    let mut groups: Vec<(Vec<Value>, Vec<&Value>)> = Vec::new();
+
+    for value in values {
+      let group = self.group_by.iter().map(|pointer| value.get(pointer.clone()).cloned().unwrap_or(Value::Null)).collect::<Vec<_>>();
+      let position = groups.iter().position(|&(ref other, _)| is_same_group(&group, other));
+      match position {
+        Some(i) => groups[i].1.push(value),
+        None => groups.push((group, vec![value]))
+      }
+    }
+
+    groups.into_iter().map(|(group, values)| {
+      let accumulated = self.accumulators.values().map(|accumulator| accumulator.accumulate(&values)).collect();
+      self.to_group_value(group, accumulated)
+    }).collect()
+  }
+
+  /// Creates the object for a single group from the grouped values, in the
+  /// order of `group_by`, and the result of every accumulator, in the order
+  /// of `accumulators`. Drivers which aggregate natively should use this to
+  /// produce their results.
+  pub fn to_group_value(&self, group: Vec<Value>, accumulated: Vec<Value>) -> Value {
+    let mut object = Object::new();
+
+    for (pointer, value) in self.group_by.iter().zip(group.into_iter()) {
+      insert_at(&mut object, pointer, value);
+    }
+
+    for (name, value) in self.accumulators.keys().zip(accumulated.into_iter()) {
+      object.insert(name.to_owned(), value);
+    }
+
+    Value::Object(object)
+  }
+}
+
+impl Accumulator {
+  /// Computes the accumulator for all of the values in a group.
+  pub fn accumulate(&self, values: &[&Value]) -> Value {
+    match *self {
+      Accumulator::Count => Value::I64(values.len() as i64),
+      Accumulator::Sum(ref pointer) => {
+        let mut integer: Option<i64> = Some(0);
+        let mut float = 0.0;
+        for value in values {
+          match value.get(pointer.clone()) {
+            Some(&Value::I64(number)) => {
+              integer = integer.and_then(|integer| integer.checked_add(number));
+              float += number as f64;
+            },
+            Some(&Value::F64(number)) => {
+              integer = None;
+              float += number;
+            },
+            _ => {}
+          }
+        }
+        integer.map_or(Value::F64(float), Value::I64)
+      },
+      Accumulator::Min(ref pointer) => extreme(values, pointer, Ordering::Less),
+      Accumulator::Max(ref pointer) => extreme(values, pointer, Ordering::Greater),
+      Accumulator::Average(ref pointer) => {
+        let numbers = values.iter().filter_map(|value| match value.get(pointer.clone()) {
+          Some(&Value::I64(number)) => Some(number as f64),
+          Some(&Value::F64(number)) => Some(number),
+          _ => None
+        }).collect::<Vec<_>>();
+        if numbers.is_empty() {
+          Value::Null
+        } else {
+          Value::F64(numbers.iter().fold(0.0, |sum, number| sum + number) / numbers.len() as f64)
+        }
+      }
+    }
+  }
+}
+
+/// Two groups are the same if all of their values are equal. Numbers are
+/// compared numerically so `I64(1)` and `F64(1.0)` are in the same group.
+fn is_same_group(a: &[Value], b: &[Value]) -> bool {
+  a.iter().zip(b.iter()).all(|(a, b)| a.compare(b) == Some(Ordering::Equal))
+}
+
+/// Finds the smallest or largest property of some values, ignoring missing
+/// and `null` properties.
+fn extreme(values: &[&Value], pointer: &Pointer, ordering: Ordering) -> Value {
+  let rule = SortRule::new(pointer.clone(), true);
+  let mut extreme: Option<&Value> = None;
+
+  for &value in values {
+    match value.get(pointer.clone()) {
+      None | Some(&Value::Null) => {},
+      Some(_) => {
+        if extreme.map_or(true, |extreme| rule.compare(value, extreme) == ordering) {
+          extreme = Some(value);
+        }
+      }
+    }
+  }
+
+  extreme.and_then(|value| value.get(pointer.clone())).cloned().unwrap_or(Value::Null)
+}
+
+/// Inserts a value into an object at a pointer, creating nested objects as
+/// needed. Values at an empty pointer are not inserted.
+fn insert_at(object: &mut Object, pointer: &[Key], value: Value) {
+  let (key, rest) = match pointer.split_first() {
+    Some(split) => split,
+    None => return
+  };
+
+  if rest.is_empty() {
+    object.insert(key.to_owned(), value);
+    return;
+  }
+
+  if let Some(&mut Value::Object(ref mut sub_object)) = object.get_mut(key) {
+    return insert_at(sub_object, rest, value);
+  }
+
+  let mut sub_object = Object::new();
+  insert_at(&mut sub_object, rest, value);
+  object.insert(key.to_owned(), Value::Object(sub_object));
+}
+
+#[cfg(test)]
+mod tests {
+  use query::{Aggregation, Accumulator};
+  use value::{Pointer, Value};
+
+  fn get_values() -> Vec<Value> {
+    vec![
+      value!({ "name" => "a", "address" => { "city" => "Paris" }, "age" => 20 }),
+      value!({ "name" => "b", "address" => { "city" => "Tokyo" }, "age" => 30.5 }),
+      value!({ "name" => "c", "address" => { "city" => "Paris" }, "age" => 40 }),
+      value!({ "name" => "d" })
+    ]
+  }
+
+  fn get_aggregation(group_by: Vec<Pointer>) -> Aggregation {
+    let mut aggregation = Aggregation::new(group_by);
+    aggregation.add_accumulator("count", Accumulator::Count);
+    aggregation.add_accumulator("sum", Accumulator::Sum(point!["age"]));
+    aggregation.add_accumulator("min", Accumulator::Min(point!["name"]));
+    aggregation.add_accumulator("max", Accumulator::Max(point!["age"]));
+    aggregation.add_accumulator("avg", Accumulator::Average(point!["age"]));
+    aggregation
+  }
+
+  #[test]
+  fn test_aggregate() {
+    assert_eq!(get_aggregation(vec![point!["address", "city"]]).aggregate(get_values().iter()), vec![
+      value!({ "address" => { "city" => "Paris" }, "count" => 2, "sum" => 60, "min" => "a", "max" => 40, "avg" => 30.0 }),
+      value!({ "address" => { "city" => "Tokyo" }, "count" => 1, "sum" => 30.5, "min" => "b", "max" => 30.5, "avg" => 30.5 }),
+      value!({ "address" => { "city" => () }, "count" => 1, "sum" => 0, "min" => "d", "max" => (), "avg" => () })
+    ]);
+  }
+
+  #[test]
+  fn test_aggregate_all() {
+    assert_eq!(get_aggregation(vec![]).aggregate(get_values().iter()), vec![
+      value!({ "count" => 4, "sum" => 90.5, "min" => "a", "max" => 40, "avg" => (90.5 / 3.0) })
+    ]);
+    assert_eq!(get_aggregation(vec![]).aggregate(Vec::new().iter()), vec![]);
+  }
+
+  #[test]
+  fn test_check() {
+    assert!(get_aggregation(vec![point!["address", "city"], point!["name"]]).check().is_ok());
+    let mut aggregation = get_aggregation(vec![]);
+    aggregation.add_accumulator("_id", Accumulator::Count);
+    assert!(aggregation.check().is_err());
+    let mut aggregation = get_aggregation(vec![point!["address", "city"]]);
+    aggregation.add_accumulator("address", Accumulator::Count);
+    assert!(aggregation.check().is_err());
+  }
+
+  #[test]
+  fn test_aggregate_numeric_groups() {
+    let mut aggregation = Aggregation::new(vec![point!["a"], point!["b"]]);
+    aggregation.add_accumulator("count", Accumulator::Count);
+    assert_eq!(aggregation.aggregate(vec![
+      value!({ "a" => 1, "b" => true }),
+      value!({ "a" => 1.0, "b" => true }),
+      value!({ "a" => 1, "b" => false })
+    ].iter()), vec![
+      value!({ "a" => 1, "b" => true, "count" => 2 }),
+      value!({ "a" => 1, "b" => false, "count" => 1 })
+    ]);
+  }
+}
@@ -0,0 +1,196 @@
+//! Opaque cursors used to paginate through sorted values without skipping.
+
+use std::fmt;
+use std::str::{self, FromStr};
+
+use linear_map::LinearMap;
+
+use error::Error;
+use query::{Condition, SortRule};
+use value::{Pointer, Value};
+
+/// Points to the position right after a value in a sorted list of values.
+/// Instead of skipping a number of values, which is slow and unstable when
+/// values are added or removed, the next page is found with a condition
+/// requiring values to come after the cursor in the sort order. This is known
+/// as keyset pagination.
+///
+/// A cursor stores the values of every sorted property for the last value of
+/// a page. When formatted as a string a cursor is opaque, users should not
+/// depend on its contents.
+///
+/// For cursors to work well the sort rules should uniquely order the values,
+/// so the last sort rule should be on a unique property like an id. Sorted
+/// properties should also never be missing or `null`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Cursor {
+  /// The values of every sorted property, in the order of the sort rules.
+  values: Vec<Value>
+}
+
+impl Cursor {
+  /// Creates a new cursor from the values of every sorted property.
+  pub fn new(values: Vec<Value>) -> Self {
+    Cursor {
+      values: values
+    }
+  }
+
+  /// Creates a cursor which points right after a value by getting the value
+  /// of every sorted property. Missing properties are stored as `null`.
+  pub fn from_value(sort: &[SortRule], value: &Value) -> Self {
+    Cursor::new(sort.iter().map(|rule| value.get(rule.property().clone()).cloned().unwrap_or(Value::Null)).collect())
+  }
+
+  /// Get the values of every sorted property.
+  pub fn values(&self) -> &Vec<Value> {
+    &self.values
+  }
+
+  /// Checks that values can be paginated with a cursor for the sort rules.
+  /// A cursor holds the sorted properties of a value, so without any sort
+  /// rules there is nothing to continue from.
+  pub fn check_sort(sort: &[SortRule]) -> Result<(), Error> {
+    if sort.is_empty() {
+      Err(Error::invalid(
+        "Cannot paginate with a cursor without any sort rules.",
+        "Try sorting by at least one property, like an id."
+      ))
+    } else {
+      Ok(())
+    }
+  }
+
+  /// Creates the condition which only passes for values coming after the
+  /// cursor when sorted by the sort rules. The sort rules must be the same as
+  /// the ones used to create the cursor.
+  ///
+  /// For the sort rules `a` ascending and `b` descending the condition is
+  /// equivalent to `a > $a OR (a = $a AND b < $b)`.
+  pub fn condition(&self, sort: &[SortRule]) -> Result<Condition, Error> {
+    try!(Cursor::check_sort(sort));
+
+    if sort.len() != self.values.len() {
+      return Err(Error::invalid(
+        format!("Cursor has {} values but there are {} sort rules.", self.values.len(), sort.len()),
+        "Try using the same sort rules which were used to get the cursor."
+      ));
+    }
+
+    let mut conditions = Vec::new();
+
+    for (i, rule) in sort.iter().enumerate() {
+      let mut and = Vec::new();
+
+      for (previous_rule, value) in sort.iter().zip(self.values.iter()).take(i) {
+        and.push(condition_at(previous_rule.property(), Condition::Equal(value.clone())));
+      }
+
+      let value = self.values[i].clone();
+      and.push(condition_at(rule.property(), if rule.is_ascending() {
+        Condition::GreaterThan(value)
+      } else {
+        Condition::LessThan(value)
+      }));
+
+      conditions.push(if and.len() == 1 { and.pop().unwrap() } else { Condition::And(and) });
+    }
+
+    Ok(if conditions.len() == 1 { conditions.pop().unwrap() } else { Condition::Or(conditions) })
+  }
+}
+
+/// Nests a condition at a pointer with `Condition::Keys`.
+fn condition_at(pointer: &Pointer, condition: Condition) -> Condition {
+  pointer.iter().rev().fold(condition, |condition, key| {
+    let mut keys = LinearMap::new();
+    keys.insert(key.to_owned(), condition);
+    Condition::Keys(keys)
+  })
+}
+
+/// Formats the cursor as an opaque hex encoded string.
+impl fmt::Display for Cursor {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let json = try!(Value::Array(self.values.clone()).to_json().map_err(|_| fmt::Error));
+    for byte in json.bytes() {
+      try!(write!(f, "{:02x}", byte));
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for Cursor {
+  type Err = Error;
+
+  fn from_str(string: &str) -> Result<Self, Error> {
+    fn invalid() -> Error {
+      Error::invalid("Cursor is invalid.", "Try using a cursor exactly as it was returned by a previous page.")
+    }
+
+    if string.len() % 2 != 0 {
+      return Err(invalid());
+    }
+
+    let mut bytes = Vec::with_capacity(string.len() / 2);
+
+    for pair in string.as_bytes().chunks(2) {
+      let pair = try!(str::from_utf8(pair).map_err(|_| invalid()));
+      bytes.push(try!(u8::from_str_radix(pair, 16).map_err(|_| invalid())));
+    }
+
+    let json = try!(String::from_utf8(bytes).map_err(|_| invalid()));
+
+    match Value::from_json(&json) {
+      Ok(Value::Array(values)) => Ok(Cursor::new(values)),
+      _ => Err(invalid())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use query::{Condition, Cursor, SortRule};
+  use value::Value;
+
+  #[test]
+  fn test_from_value() {
+    let sort = vec![SortRule::new(point!["a", "b"], true), SortRule::new(point!["c"], false)];
+    assert_eq!(Cursor::from_value(&sort, &value!({ "a" => { "b" => 2 } })), Cursor::new(vec![Value::I64(2), Value::Null]));
+  }
+
+  #[test]
+  fn test_condition() {
+    let sort = vec![SortRule::new(point!["a"], true), SortRule::new(point!["b", "c"], false)];
+    assert_eq!(Cursor::new(vec![value!(1), value!("x")]).condition(&sort).unwrap(), Condition::Or(vec![
+      Condition::Keys(linear_map! {
+        str!("a") => Condition::GreaterThan(Value::I64(1))
+      }),
+      Condition::And(vec![
+        Condition::Keys(linear_map! {
+          str!("a") => Condition::Equal(Value::I64(1))
+        }),
+        Condition::Keys(linear_map! {
+          str!("b") => Condition::Keys(linear_map! {
+            str!("c") => Condition::LessThan(Value::String(str!("x")))
+          })
+        })
+      ])
+    ]));
+    assert_eq!(Cursor::new(vec![value!(1)]).condition(&sort[..1]).unwrap(), Condition::Keys(linear_map! {
+      str!("a") => Condition::GreaterThan(Value::I64(1))
+    }));
+    Cursor::new(vec![]).condition(&[]).unwrap_err().expect("without any sort rules");
+    Cursor::new(vec![value!(1)]).condition(&sort).unwrap_err().expect("Cursor has 1 values but there are 2 sort rules.");
+  }
+
+  #[test]
+  fn test_string() {
+    let cursor = Cursor::new(vec![value!(1), value!("hello"), value!()]);
+    assert_eq!(cursor.to_string().parse::<Cursor>().unwrap(), cursor);
+    assert_eq!(Cursor::new(vec![value!(1)]).to_string(), "5b315d");
+    "5b315".parse::<Cursor>().unwrap_err().expect("Cursor is invalid.");
+    "zz".parse::<Cursor>().unwrap_err().expect("Cursor is invalid.");
+    "3130".parse::<Cursor>().unwrap_err().expect("Cursor is invalid.");
+  }
+}
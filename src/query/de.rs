@@ -0,0 +1,350 @@
+use linear_map::LinearMap;
+use regex::Regex;
+use serde::ser::{Serialize, Serializer};
+use serde::de::{Deserialize, Deserializer, Error as DeError};
+
+use error::Error;
+use query::Condition;
+use value::{Object, Value};
+
+const OPERATORS_HINT: &'static str = "Try using one of the following operators: `$and`, `$or`, `$not`, `$eq`, `$gt`, `$gte`, `$lt`, `$lte`, `$in`, `$nin`, `$exists`, `$regex`, or `$contains`.";
+
+impl Condition {
+  /// Parses a condition from a filter. The filter syntax is similar to the
+  /// one used by MongoDB.
+  ///
+  /// A filter is an object where every property must pass for the condition
+  /// to pass. Properties starting with `$` are operators, all other
+  /// properties are conditions on the property of the same name in the
+  /// compared value. The condition on a property is either another filter or
+  /// a non-object value which the property must be equal to. An empty filter
+  /// always passes, and the booleans `true` and `false` may be used in place
+  /// of a filter.
+  ///
+  /// The available operators are:
+  ///
+  /// - `$and`, `$or`: An array of filters which must all, or any, pass.
+  /// - `$not`: A filter which must not pass.
+  /// - `$eq`, `$gt`, `$gte`, `$lt`, `$lte`: A value which the compared value
+  ///   must be equal to, greater than, greater than or equal to, less than,
+  ///   or less than or equal to. Use `$eq` to compare with an object.
+  /// - `$in`, `$nin`: An array of values which the compared value must, or
+  ///   must not, be equal to one of.
+  /// - `$exists`: A boolean of whether or not the compared value must exist.
+  /// - `$regex`: A regular expression string which the compared value must
+  ///   match.
+  /// - `$contains`: A value which must be an item of the compared array.
+  ///
+  /// # Example
+  /// ```rust
+  /// #[macro_use(value)]
+  /// extern crate ardite;
+  /// #[macro_use(linear_map)]
+  /// extern crate linear_map;
+  ///
+  /// use ardite::query::Condition;
+  /// use ardite::value::Value;
+  ///
+  /// # fn main() {
+  ///
+  /// let filter = value!({
+  ///   "age" => { "$gt" => 18 },
+  ///   "status" => "active"
+  /// });
+  ///
+  /// assert_eq!(Condition::from_value(filter).unwrap(), Condition::Keys(linear_map! {
+  ///   String::from("age") => Condition::GreaterThan(Value::I64(18)),
+  ///   String::from("status") => Condition::Equal(Value::String(String::from("active")))
+  /// }));
+  ///
+  /// # }
+  /// ```
+  pub fn from_value(value: Value) -> Result<Condition, Error> {
+    match value {
+      Value::Boolean(true) => Ok(Condition::True),
+      Value::Boolean(false) => Ok(Condition::False),
+      Value::Object(object) => {
+        let mut keys = LinearMap::new();
+        let mut conditions = Vec::new();
+
+        for (key, value) in object {
+          if key.starts_with('$') {
+            conditions.push(try!(operator_from_value(&key, value)));
+          } else {
+            keys.insert(key, try!(property_from_value(value)));
+          }
+        }
+
+        if !keys.is_empty() {
+          conditions.insert(0, Condition::Keys(keys));
+        }
+
+        Ok(match conditions.len() {
+          0 => Condition::True,
+          1 => conditions.pop().unwrap(),
+          _ => Condition::And(conditions)
+        })
+      },
+      _ => Err(Error::invalid(
+        format!("Filter {} is not an object.", value.to_json().unwrap_or_else(|_| format!("{:?}", value))),
+        "Try using an object filter like `{ \"age\": { \"$gt\": 18 } }`."
+      ))
+    }
+  }
+
+  /// Parses a condition from a JSON filter string. See
+  /// `Condition::from_value` for the filter syntax.
+  pub fn from_json(json: &str) -> Result<Condition, Error> {
+    Condition::from_value(try!(Value::from_json(json)))
+  }
+
+  /// Serializes a condition to a filter. Parsing the filter results in an
+  /// equivalent condition.
+  pub fn to_value(&self) -> Value {
+    match *self {
+      Condition::True => Value::Boolean(true),
+      Condition::False => Value::Boolean(false),
+      Condition::Not(ref condition) => operator_to_value("$not", condition.to_value()),
+      Condition::And(ref conditions) => operator_to_value("$and", Value::Array(conditions.iter().map(Condition::to_value).collect())),
+      Condition::Or(ref conditions) => operator_to_value("$or", Value::Array(conditions.iter().map(Condition::to_value).collect())),
+      Condition::Keys(ref keys) => {
+        let mut object = Object::new();
+        for (key, condition) in keys.iter() {
+          object.insert(key.to_owned(), property_to_value(condition));
+        }
+        Value::Object(object)
+      },
+      Condition::Equal(ref value) => operator_to_value("$eq", value.clone()),
+      Condition::GreaterThan(ref value) => operator_to_value("$gt", value.clone()),
+      Condition::GreaterThanOrEqual(ref value) => operator_to_value("$gte", value.clone()),
+      Condition::LessThan(ref value) => operator_to_value("$lt", value.clone()),
+      Condition::LessThanOrEqual(ref value) => operator_to_value("$lte", value.clone()),
+      Condition::In(ref values) => operator_to_value("$in", Value::Array(values.clone())),
+      Condition::NotIn(ref values) => operator_to_value("$nin", Value::Array(values.clone())),
+      Condition::Exists => operator_to_value("$exists", Value::Boolean(true)),
+      Condition::Matches(ref regex) => operator_to_value("$regex", Value::String(regex.as_str().to_owned())),
+      Condition::Contains(ref value) => operator_to_value("$contains", value.clone())
+    }
+  }
+
+  /// Serializes a condition to a JSON filter string.
+  pub fn to_json(&self) -> Result<String, Error> {
+    self.to_value().to_json()
+  }
+}
+
+/// Parses the condition for a single operator.
+fn operator_from_value(operator: &str, value: Value) -> Result<Condition, Error> {
+  match operator {
+    "$and" => Ok(Condition::And(try!(filters_from_value(operator, value)))),
+    "$or" => Ok(Condition::Or(try!(filters_from_value(operator, value)))),
+    "$not" => Ok(Condition::Not(Box::new(try!(Condition::from_value(value))))),
+    "$eq" => Ok(Condition::Equal(value)),
+    "$gt" => Ok(Condition::GreaterThan(value)),
+    "$gte" => Ok(Condition::GreaterThanOrEqual(value)),
+    "$lt" => Ok(Condition::LessThan(value)),
+    "$lte" => Ok(Condition::LessThanOrEqual(value)),
+    "$in" => Ok(Condition::In(try!(array_from_value(operator, value)))),
+    "$nin" => Ok(Condition::NotIn(try!(array_from_value(operator, value)))),
+    "$exists" => match value {
+      Value::Boolean(true) => Ok(Condition::Exists),
+      Value::Boolean(false) => Ok(Condition::Not(Box::new(Condition::Exists))),
+      _ => Err(Error::invalid(
+        "Operator `$exists` must be a boolean.",
+        "Try using `{ \"$exists\": true }` or `{ \"$exists\": false }`."
+      ))
+    },
+    "$regex" => match value {
+      Value::String(pattern) => Regex::new(&pattern).map(Condition::Matches).map_err(|error| Error::invalid(
+        format!("Operator `$regex` has an invalid pattern /{}/: {}", pattern, error),
+        "Try fixing the syntax of your regular expression."
+      )),
+      _ => Err(Error::invalid(
+        "Operator `$regex` must be a string.",
+        "Try using a regular expression string like `{ \"$regex\": \"^hello\" }`."
+      ))
+    },
+    "$contains" => Ok(Condition::Contains(value)),
+    _ => Err(Error::invalid(format!("Unknown filter operator `{}`.", operator), OPERATORS_HINT))
+  }
+}
+
+/// Parses the condition for a property. Objects are filters, while any other
+/// value must be equal to the property.
+fn property_from_value(value: Value) -> Result<Condition, Error> {
+  match value {
+    Value::Object(_) => Condition::from_value(value),
+    _ => Ok(Condition::Equal(value))
+  }
+}
+
+/// Parses an array of filters for an operator.
+fn filters_from_value(operator: &str, value: Value) -> Result<Vec<Condition>, Error> {
+  let mut conditions = Vec::new();
+  for value in try!(array_from_value(operator, value)) {
+    conditions.push(try!(Condition::from_value(value)));
+  }
+  Ok(conditions)
+}
+
+/// Gets the array of values for an operator.
+fn array_from_value(operator: &str, value: Value) -> Result<Vec<Value>, Error> {
+  match value {
+    Value::Array(values) => Ok(values),
+    _ => Err(Error::invalid(
+      format!("Operator `{}` must be an array.", operator),
+      format!("Try using an array like `{{ \"{}\": [1, 2, 3] }}`.", operator)
+    ))
+  }
+}
+
+/// Creates an object with a single operator property.
+fn operator_to_value(operator: &str, value: Value) -> Value {
+  let mut object = Object::new();
+  object.insert(operator.to_owned(), value);
+  Value::Object(object)
+}
+
+/// Serializes the condition for a property. Equality with a non-object value
+/// is shortened to the value itself. Because booleans are values in this
+/// position, `Condition::True` and `Condition::False` are written as filters.
+fn property_to_value(condition: &Condition) -> Value {
+  match *condition {
+    Condition::True => Value::Object(Object::new()),
+    Condition::False => operator_to_value("$not", Value::Object(Object::new())),
+    Condition::Equal(Value::Object(_)) => condition.to_value(),
+    Condition::Equal(ref value) => value.clone(),
+    _ => condition.to_value()
+  }
+}
+
+impl Serialize for Condition {
+  fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error> where S: Serializer {
+    self.to_value().serialize(serializer)
+  }
+}
+
+impl Deserialize for Condition {
+  fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error> where D: Deserializer {
+    let value = try!(Value::deserialize(deserializer));
+    Condition::from_value(value).map_err(|error| DeError::custom(match error.hint() {
+      Some(hint) => format!("{} {}", error.message(), hint),
+      None => error.message().to_owned()
+    }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use regex::Regex;
+  use serde_json;
+  use serde_yaml;
+
+  use query::Condition;
+  use value::Value;
+
+  #[test]
+  fn test_from_value() {
+    assert_eq!(Condition::from_value(value!(true)).unwrap(), Condition::True);
+    assert_eq!(Condition::from_value(value!(false)).unwrap(), Condition::False);
+    assert_eq!(Condition::from_value(value!({})).unwrap(), Condition::True);
+    assert_eq!(Condition::from_value(value!({ "a" => 1 })).unwrap(), Condition::Keys(linear_map! {
+      str!("a") => Condition::Equal(Value::I64(1))
+    }));
+    assert_eq!(Condition::from_value(value!({
+      "a" => { "$gt" => 1, "$lte" => 5.5 },
+      "b" => { "c" => { "$in" => ["x", "y"] }, "d" => true },
+      "e" => { "$exists" => false, "$regex" => "^hello" },
+      "f" => { "$nin" => [], "$contains" => 2, "$eq" => { "g" => () } },
+      "$or" => [{ "h" => () }, false],
+      "$not" => { "i" => { "$lt" => 0 } }
+    })).unwrap(), Condition::And(vec![
+      Condition::Keys(linear_map! {
+        str!("a") => Condition::And(vec![
+          Condition::GreaterThan(Value::I64(1)),
+          Condition::LessThanOrEqual(Value::F64(5.5))
+        ]),
+        str!("b") => Condition::Keys(linear_map! {
+          str!("c") => Condition::In(vec![Value::String(str!("x")), Value::String(str!("y"))]),
+          str!("d") => Condition::Equal(Value::Boolean(true))
+        }),
+        str!("e") => Condition::And(vec![
+          Condition::Not(Box::new(Condition::Exists)),
+          Condition::Matches(Regex::new("^hello").unwrap())
+        ]),
+        str!("f") => Condition::And(vec![
+          Condition::NotIn(vec![]),
+          Condition::Contains(Value::I64(2)),
+          Condition::Equal(value!({ "g" => () }))
+        ])
+      }),
+      Condition::Or(vec![
+        Condition::Keys(linear_map! {
+          str!("h") => Condition::Equal(Value::Null)
+        }),
+        Condition::False
+      ]),
+      Condition::Not(Box::new(Condition::Keys(linear_map! {
+        str!("i") => Condition::LessThan(Value::I64(0))
+      })))
+    ]));
+  }
+
+  #[test]
+  fn test_from_value_errors() {
+    Condition::from_value(value!(5)).unwrap_err().expect("Filter 5 is not an object.");
+    Condition::from_value(value!({ "a" => { "$yolo" => 5 } })).unwrap_err().expect("Unknown filter operator `\\$yolo`.");
+    Condition::from_value(value!({ "$and" => {} })).unwrap_err().expect("Operator `\\$and` must be an array.");
+    Condition::from_value(value!({ "$or" => [5] })).unwrap_err().expect("Filter 5 is not an object.");
+    Condition::from_value(value!({ "a" => { "$in" => 5 } })).unwrap_err().expect("Operator `\\$in` must be an array.");
+    Condition::from_value(value!({ "a" => { "$exists" => 1 } })).unwrap_err().expect("Operator `\\$exists` must be a boolean.");
+    Condition::from_value(value!({ "a" => { "$regex" => 1 } })).unwrap_err().expect("Operator `\\$regex` must be a string.");
+    Condition::from_value(value!({ "a" => { "$regex" => "(" } })).unwrap_err().expect("invalid pattern");
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let conditions = vec![
+      Condition::True,
+      Condition::False,
+      Condition::Equal(Value::I64(5)),
+      Condition::Not(Box::new(Condition::Or(vec![Condition::True, Condition::False]))),
+      Condition::Keys(linear_map! {
+        str!("a") => Condition::Equal(Value::String(str!("hello"))),
+        str!("b") => Condition::Equal(value!({ "c" => 1 })),
+        str!("d") => Condition::Keys(linear_map! {
+          str!("e") => Condition::GreaterThanOrEqual(Value::F64(2.5)),
+          str!("f") => Condition::And(vec![Condition::Exists, Condition::Matches(Regex::new("^a").unwrap())]),
+          str!("g") => Condition::True
+        }),
+        str!("h") => Condition::In(vec![Value::I64(1), Value::Null]),
+        str!("i") => Condition::Not(Box::new(Condition::Contains(Value::Boolean(false))))
+      })
+    ];
+    for condition in conditions {
+      assert_eq!(Condition::from_value(condition.to_value()).unwrap(), condition);
+      assert_eq!(Condition::from_json(&condition.to_json().unwrap()).unwrap(), condition);
+    }
+    assert_eq!(
+      Condition::Keys(linear_map! { str!("a") => Condition::False }).to_json().unwrap(),
+      r#"{"a":{"$not":{}}}"#
+    );
+  }
+
+  #[test]
+  fn test_serde() {
+    assert_eq!(
+      serde_json::from_str::<Condition>(r#"{"age":{"$gt":18}}"#).unwrap(),
+      Condition::Keys(linear_map! { str!("age") => Condition::GreaterThan(Value::I64(18)) })
+    );
+    assert_eq!(
+      serde_yaml::from_str::<Condition>("age:\n  $lt: 65\n").unwrap(),
+      Condition::Keys(linear_map! { str!("age") => Condition::LessThan(Value::I64(65)) })
+    );
+    assert!(serde_json::from_str::<Condition>(r#"{"age":{"$yolo":18}}"#).is_err());
+    assert_eq!(
+      serde_json::to_string(&Condition::Keys(linear_map! { str!("age") => Condition::GreaterThan(Value::I64(18)) })).unwrap(),
+      r#"{"age":{"$gt":18}}"#
+    );
+  }
+}
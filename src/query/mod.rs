@@ -0,0 +1,11 @@
+//! Defines complex queries over Ardite driver data structures.
+
+mod query;
+mod de;
+mod cursor;
+mod aggregate;
+pub mod sql;
+
+pub use query::query::*;
+pub use query::cursor::Cursor;
+pub use query::aggregate::{Aggregation, Accumulator};
@@ -0,0 +1,464 @@
+//! The types which make up a query, like conditions, sorting rules, and
+//! ranges.
+//!
+//! Alongside the definitions, this module implements the behaviour of every
+//! query construct on plain `Value`s. Drivers which can not evaluate a query
+//! natively may use these implementations, and all other drivers should
+//! produce the same results.
+
+use std::cmp::Ordering;
+use std::convert::From;
+use linear_map::LinearMap;
+use regex::Regex;
+
+use error::{Error, BadRange};
+use value::{Key, Pointer, Object, Value};
+
+/// A condition which will resolve to a boolean value after comparing a certain
+/// value with a set rule.
+///
+/// Comparisons between integers and floats are numeric, so `I64(1)` is equal
+/// to `F64(1.0)`. Values of different types (other than numbers) are never
+/// greater or less than each other, so ordering conditions comparing them
+/// always fail.
+///
+/// A condition may be parsed from, and serialized to, a filter syntax similar
+/// to the one used by MongoDB. See `Condition::from_value` for the details.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Condition {
+  /// The condition always passes.
+  True,
+  /// The condition always fails.
+  False,
+  /// Inverts a condition.
+  Not(Box<Condition>),
+  /// Composes many conditions. They all must be true for the condition to be
+  /// true.
+  And(Vec<Condition>),
+  /// Composes many conditions. Only one must be true for the condition to be
+  /// true.
+  Or(Vec<Condition>),
+  /// Partial conditions on some keys of an object.
+  Keys(LinearMap<Key, Condition>),
+  /// If the compared value is exactly equal to this one, the condition passes.
+  Equal(Value),
+  /// If the compared value is greater than this one, the condition passes.
+  GreaterThan(Value),
+  /// If the compared value is greater than or equal to this one, the
+  /// condition passes.
+  GreaterThanOrEqual(Value),
+  /// If the compared value is less than this one, the condition passes.
+  LessThan(Value),
+  /// If the compared value is less than or equal to this one, the condition
+  /// passes.
+  LessThanOrEqual(Value),
+  /// If the compared value is equal to any of these values, the condition
+  /// passes.
+  In(Vec<Value>),
+  /// If the compared value is not equal to any of these values, the condition
+  /// passes.
+  NotIn(Vec<Value>),
+  /// If the compared value exists, the condition passes. Only useful inside
+  /// `Condition::Keys` as a property which is `null` still exists.
+  Exists,
+  /// If the compared value is a string matching this regular expression, the
+  /// condition passes.
+  Matches(Regex),
+  /// If the compared value is an array with an item equal to this value, the
+  /// condition passes.
+  Contains(Value)
+}
+
+impl Condition {
+  /// Tests if a value passes the condition. When a `Condition::Keys` refers to
+  /// a property which does not exist, the property is tested as `null` by
+  /// every condition except `Condition::Exists`.
+  pub fn is_true(&self, value: &Value) -> bool {
+    self.is_true_option(Some(value))
+  }
+
+  /// Tests if a value, which may not exist, passes the condition.
+  fn is_true_option(&self, value: Option<&Value>) -> bool {
+    let null = Value::Null;
+    let compared = value.unwrap_or(&null);
+    let is_equal = |other: &Value| compared.compare(other) == Some(Ordering::Equal);
+    match *self {
+      Condition::True => true,
+      Condition::False => false,
+      Condition::Not(ref condition) => !condition.is_true_option(value),
+      Condition::And(ref conditions) => conditions.iter().all(|condition| condition.is_true_option(value)),
+      Condition::Or(ref conditions) => conditions.iter().any(|condition| condition.is_true_option(value)),
+      Condition::Keys(ref keys) => keys.iter().all(|(key, condition)| {
+        condition.is_true_option(value.and_then(|value| value.get(vec![key.to_owned()])))
+      }),
+      Condition::Equal(ref other) => is_equal(other),
+      Condition::GreaterThan(ref other) => compared.compare(other) == Some(Ordering::Greater),
+      Condition::GreaterThanOrEqual(ref other) => is_equal(other) || compared.compare(other) == Some(Ordering::Greater),
+      Condition::LessThan(ref other) => compared.compare(other) == Some(Ordering::Less),
+      Condition::LessThanOrEqual(ref other) => is_equal(other) || compared.compare(other) == Some(Ordering::Less),
+      Condition::In(ref others) => others.iter().any(|other| is_equal(other)),
+      Condition::NotIn(ref others) => !others.iter().any(|other| is_equal(other)),
+      Condition::Exists => value.is_some(),
+      Condition::Matches(ref regex) => match *compared {
+        Value::String(ref string) => regex.is_match(string),
+        _ => false
+      },
+      Condition::Contains(ref other) => match *compared {
+        Value::Array(ref array) => array.iter().any(|item| item.compare(other) == Some(Ordering::Equal)),
+        _ => false
+      }
+    }
+  }
+}
+
+impl Default for Condition {
+  fn default() -> Self {
+    Condition::True
+  }
+}
+
+/// Specifies the order in which a property of a value should be ordered.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SortRule {
+  /// The exacty property to order by.
+  property: Pointer,
+  /// The direction to order the property in.
+  direction: SortDirection
+}
+
+impl SortRule {
+  /// Create a new sorting rule from the property pointer and a boolean
+  /// specifying if we are ascending or descending.
+  pub fn new(property: Pointer, ascending: bool) -> Self {
+    SortRule {
+      property: property,
+      direction: if ascending { SortDirection::Ascending } else { SortDirection::Descending }
+    }
+  }
+
+  /// Get the property the struct is sorting against.
+  pub fn property(&self) -> &Pointer {
+    &self.property
+  }
+
+  /// Is the struct sorting the property in ascending order?
+  pub fn is_ascending(&self) -> bool {
+    if let SortDirection::Ascending = self.direction {
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Is the struct sorting the property in descending order?
+  pub fn is_descending(&self) -> bool {
+    if let SortDirection::Descending = self.direction {
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Compares the property of two values in the direction of the rule.
+  /// Missing properties are compared as `null`. When the properties have
+  /// different types they are ordered by type in the following order (the
+  /// same order MongoDB uses):
+  ///
+  /// 1. Null.
+  /// 2. Numbers.
+  /// 3. Strings.
+  /// 4. Objects.
+  /// 5. Arrays.
+  /// 6. Booleans.
+  pub fn compare(&self, a: &Value, b: &Value) -> Ordering {
+    fn type_order(value: &Value) -> u8 {
+      match *value {
+        Value::Null => 0,
+        Value::I64(_) | Value::F64(_) => 1,
+        Value::String(_) => 2,
+        Value::Object(_) => 3,
+        Value::Array(_) => 4,
+        Value::Boolean(_) => 5
+      }
+    }
+
+    let null = Value::Null;
+    let a = a.get(self.property.clone()).unwrap_or(&null);
+    let b = b.get(self.property.clone()).unwrap_or(&null);
+
+    let ordering = match type_order(a).cmp(&type_order(b)) {
+      Ordering::Equal => a.compare(b).unwrap_or(Ordering::Equal),
+      ordering => ordering
+    };
+
+    if self.is_descending() { ordering.reverse() } else { ordering }
+  }
+}
+
+/// The direction in which an order occurs.
+#[derive(Clone, PartialEq, Debug)]
+enum SortDirection {
+  Ascending,
+  Descending
+}
+
+/// Specifies a positive integer range in a traditional SQL format.
+pub struct Range {
+  /// How many items should be included in this range.
+  limit: Option<u64>,
+  /// How many items should be skipped from the full set in this range.
+  skip: Option<u64>
+}
+
+impl Range {
+  /// Creates a new range from a limit and a skip.
+  pub fn new(skip: Option<u64>, limit: Option<u64>) -> Self {
+    Range {
+      limit: limit,
+      skip: skip
+    }
+  }
+
+  /// Get the limit of the range.
+  pub fn limit(&self) -> Option<u64> {
+    self.limit
+  }
+
+  /// Get how many items the range skips over.
+  pub fn skip(&self) -> Option<u64> {
+    self.skip
+  }
+
+  /// Checks that the range can be satisfied for a total number of items. If
+  /// the range skips beyond the total a `BadRange` error is returned.
+  pub fn check(&self, total: u64) -> Result<(), Error> {
+    match self.skip {
+      Some(skip) if skip > total => Err(
+        Error::new(BadRange, format!("Cannot skip {} items when there are only {} items.", skip, total))
+        .set_hint(format!("Try skipping at most {} items.", total))
+      ),
+      _ => Ok(())
+    }
+  }
+}
+
+impl Default for Range {
+  fn default() -> Self {
+    Range::new(None, None)
+  }
+}
+
+/// Specifies a complex driver query. The query is structured like a tree
+/// except each node is unaware of its name (or if it even has a name). It
+/// cannot be expected that a `Query` tree will map 1 to 1 with a `Value` tree.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Query {
+  /// Queries a single value.
+  All,
+  /// Queries some partial properties of an object.
+  Keys(LinearMap<Key, Query>)
+}
+
+impl Query {
+  /// Selects the parts of a value described by the query. Queried properties
+  /// which do not exist in the value are left out. Array items are queried by
+  /// their index. Returns `None` if the value can not be queried at all, like
+  /// when querying the properties of a primitive.
+  pub fn select(&self, value: &Value) -> Option<Value> {
+    match (self, value) {
+      (&Query::All, _) => Some(value.clone()),
+      (&Query::Keys(ref keys), &Value::Object(ref object)) => {
+        let mut selected = Object::new();
+        for (key, query) in keys.iter() {
+          if let Some(sub_value) = object.get(key).and_then(|sub_value| query.select(sub_value)) {
+            selected.insert(key.to_owned(), sub_value);
+          }
+        }
+        Some(Value::Object(selected))
+      },
+      (&Query::Keys(ref keys), &Value::Array(ref array)) => Some(Value::Array(
+        array.iter().enumerate().filter_map(|(i, item)| {
+          keys.get(&i.to_string()).and_then(|query| query.select(item))
+        }).collect()
+      )),
+      (&Query::Keys(_), _) => None
+    }
+  }
+}
+
+impl Default for Query {
+  fn default() -> Self {
+    Query::All
+  }
+}
+
+impl From<Pointer> for Query {
+  fn from(pointer: Pointer) -> Self {
+    // Reverse loop through the pointer to construct the query.
+    pointer.iter().rev().fold(Query::All, |acc, key| {
+      let mut map = LinearMap::new();
+      map.insert(key.to_owned(), acc);
+      Query::Keys(map)
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cmp::Ordering::{Less, Equal, Greater};
+
+  use regex::Regex;
+
+  use error::BadRange;
+  use query::{Condition, SortRule, Range, Query};
+  use value::Value;
+
+  #[test]
+  fn test_condition_is_true() {
+    let value = value!({
+      "a" => 1,
+      "b" => "hello",
+      "c" => {
+        "d" => 2.0,
+        "e" => [1, 2, 3]
+      }
+    });
+    assert!(Condition::True.is_true(&value));
+    assert!(!Condition::False.is_true(&value));
+    assert!(Condition::Not(Box::new(Condition::False)).is_true(&value));
+    assert!(Condition::And(vec![]).is_true(&value));
+    assert!(!Condition::And(vec![Condition::True, Condition::False]).is_true(&value));
+    assert!(!Condition::Or(vec![]).is_true(&value));
+    assert!(Condition::Or(vec![Condition::False, Condition::True]).is_true(&value));
+    assert!(Condition::Equal(value.clone()).is_true(&value));
+    assert!(Condition::Keys(linear_map! {
+      str!("a") => Condition::Equal(Value::F64(1.0)),
+      str!("b") => Condition::Equal(Value::String(str!("hello"))),
+      str!("c") => Condition::Keys(linear_map! {
+        str!("d") => Condition::Equal(Value::I64(2)),
+        str!("e") => Condition::Keys(linear_map! {
+          str!("1") => Condition::Equal(Value::I64(2))
+        })
+      })
+    }).is_true(&value));
+    assert!(!Condition::Keys(linear_map! {
+      str!("b") => Condition::Equal(Value::String(str!("world")))
+    }).is_true(&value));
+    assert!(Condition::Keys(linear_map! {
+      str!("z") => Condition::Equal(Value::Null)
+    }).is_true(&value));
+    assert!(!Condition::Keys(linear_map! {
+      str!("a") => Condition::Keys(linear_map! {
+        str!("b") => Condition::Equal(Value::I64(1))
+      })
+    }).is_true(&value));
+  }
+
+  #[test]
+  fn test_condition_is_true_operators() {
+    let value = value!({
+      "a" => 5,
+      "b" => "hello world",
+      "c" => [1, 2.5, "three"],
+      "d" => ()
+    });
+    let is_true = |key: &str, condition: Condition| Condition::Keys(linear_map! { str!(key) => condition }).is_true(&value);
+    assert!(is_true("a", Condition::GreaterThan(Value::I64(4))));
+    assert!(is_true("a", Condition::GreaterThan(Value::F64(4.5))));
+    assert!(!is_true("a", Condition::GreaterThan(Value::I64(5))));
+    assert!(!is_true("a", Condition::GreaterThan(Value::String(str!("4")))));
+    assert!(is_true("a", Condition::GreaterThanOrEqual(Value::F64(5.0))));
+    assert!(is_true("a", Condition::LessThan(Value::I64(6))));
+    assert!(!is_true("a", Condition::LessThan(Value::I64(5))));
+    assert!(is_true("a", Condition::LessThanOrEqual(Value::I64(5))));
+    assert!(!is_true("z", Condition::LessThan(Value::I64(5))));
+    assert!(is_true("b", Condition::GreaterThan(Value::String(str!("hello")))));
+    assert!(is_true("a", Condition::In(vec![Value::I64(1), Value::F64(5.0)])));
+    assert!(!is_true("a", Condition::In(vec![])));
+    assert!(is_true("a", Condition::NotIn(vec![Value::I64(1), Value::String(str!("5"))])));
+    assert!(!is_true("a", Condition::NotIn(vec![Value::I64(5)])));
+    assert!(is_true("a", Condition::Exists));
+    assert!(is_true("d", Condition::Exists));
+    assert!(!is_true("z", Condition::Exists));
+    assert!(is_true("z", Condition::Not(Box::new(Condition::Exists))));
+    assert!(is_true("b", Condition::Matches(Regex::new("^hello").unwrap())));
+    assert!(!is_true("b", Condition::Matches(Regex::new("^world").unwrap())));
+    assert!(!is_true("a", Condition::Matches(Regex::new("5").unwrap())));
+    assert!(is_true("c", Condition::Contains(Value::F64(1.0))));
+    assert!(is_true("c", Condition::Contains(Value::String(str!("three")))));
+    assert!(!is_true("c", Condition::Contains(Value::I64(3))));
+    assert!(!is_true("b", Condition::Contains(Value::String(str!("hello")))));
+  }
+
+  #[test]
+  fn test_sort_rule_compare() {
+    let a = value!({ "a" => 1, "b" => { "c" => "hello" } });
+    let b = value!({ "a" => 2.5, "b" => { "c" => "world" } });
+    let c = value!({ "a" => "1", "b" => true });
+    assert_eq!(SortRule::new(point!["a"], true).compare(&a, &b), Less);
+    assert_eq!(SortRule::new(point!["a"], false).compare(&a, &b), Greater);
+    assert_eq!(SortRule::new(point!["a"], true).compare(&a, &a), Equal);
+    assert_eq!(SortRule::new(point!["b", "c"], true).compare(&a, &b), Less);
+    assert_eq!(SortRule::new(point!["a"], true).compare(&b, &c), Less);
+    assert_eq!(SortRule::new(point!["b", "c"], true).compare(&c, &a), Less);
+  }
+
+  #[test]
+  fn test_range_check() {
+    assert!(Range::new(None, Some(5)).check(0).is_ok());
+    assert!(Range::new(Some(3), None).check(3).is_ok());
+    assert!(Range::new(Some(2), Some(5)).check(3).is_ok());
+    let error = Range::new(Some(4), Some(5)).check(3).unwrap_err();
+    assert_eq!(error.code(), &BadRange);
+    error.expect("Cannot skip 4 items when there are only 3 items.");
+  }
+
+  #[test]
+  fn test_query_select() {
+    let value = value!({
+      "a" => 1,
+      "b" => { "c" => 2, "d" => 3 },
+      "e" => [{ "f" => 4 }, { "f" => 5 }, 6]
+    });
+    assert_eq!(Query::All.select(&value), Some(value.clone()));
+    assert_eq!(Query::Keys(linear_map! {}).select(&value), Some(value!({})));
+    assert_eq!(Query::Keys(linear_map! {
+      str!("a") => Query::All,
+      str!("b") => Query::Keys(linear_map! {
+        str!("d") => Query::All
+      }),
+      str!("e") => Query::Keys(linear_map! {
+        str!("1") => Query::Keys(linear_map! {
+          str!("f") => Query::All
+        }),
+        str!("2") => Query::All
+      }),
+      str!("z") => Query::All
+    }).select(&value), Some(value!({
+      "a" => 1,
+      "b" => { "d" => 3 },
+      "e" => [{ "f" => 5 }, 6]
+    })));
+    assert_eq!(Query::Keys(linear_map! {
+      str!("a") => Query::Keys(linear_map! {
+        str!("b") => Query::All
+      })
+    }).select(&value), Some(value!({})));
+    assert_eq!(Query::Keys(linear_map! {}).select(&value!(5)), None);
+  }
+
+  #[test]
+  fn test_from_pointer() {
+    assert_eq!(Query::from(point!["hello", "good", "world"]), Query::Keys(linear_map! {
+      str!("hello") => Query::Keys(linear_map! {
+        str!("good") => Query::Keys(linear_map! {
+          str!("world") => Query::All
+        })
+      })
+    }));
+    assert_eq!(Query::from(point!["good"]), Query::Keys(linear_map! {
+      str!("good") => Query::All
+    }));
+    assert_eq!(Query::from(point![]), Query::All);
+  }
+}
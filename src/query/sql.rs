@@ -0,0 +1,771 @@
+//! Generates SQL for Ardite queries in the dialect of a few popular SQL
+//! databases. SQL drivers should use this module instead of writing their own
+//! translation so that every SQL driver behaves the same.
+//!
+//! Every type is expected to map to a table with a column for every top level
+//! property. Properties nested in a column are accessed as JSON, so columns
+//! holding objects or arrays should be `jsonb` in PostgreSQL, `JSON` in MySQL,
+//! and JSON text in SQLite.
+//!
+//! Values are never written into the SQL, they are always bound as
+//! parameters. In PostgreSQL and MySQL values compared with JSON are bound as
+//! JSON text. All other values are bound as they are, and the driver should
+//! convert them to the closest native type.
+
+use std::i64;
+
+use error::Error;
+use query::{Condition, SortRule, Range, Query};
+use value::{Key, Object, Value};
+
+/// A dialect of SQL.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Dialect {
+  /// [PostgreSQL][1] uses `"` quotes, `$1` placeholders, and `jsonb` operators.
+  ///
+  /// [1]: http://www.postgresql.org/docs/current/static/functions-json.html
+  Postgres,
+  /// [MySQL][1] uses `` ` `` quotes, `?` placeholders, and `JSON` functions.
+  ///
+  /// [1]: https://dev.mysql.com/doc/refman/5.7/en/json-functions.html
+  MySql,
+  /// [SQLite][1] uses `"` quotes, `?` placeholders, and JSON1 functions. SQLite
+  /// does not define a `regexp` function, so drivers must add one.
+  ///
+  /// [1]: https://www.sqlite.org/json1.html
+  Sqlite
+}
+
+/// SQL text and the parameters bound to its placeholders, in order.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Statement {
+  /// The SQL text.
+  sql: String,
+  /// The parameters for every placeholder in the SQL.
+  params: Vec<Value>
+}
+
+impl Statement {
+  /// Get the SQL text.
+  pub fn sql(&self) -> &str {
+    &self.sql
+  }
+
+  /// Get the parameters for every placeholder in the SQL.
+  pub fn params(&self) -> &Vec<Value> {
+    &self.params
+  }
+
+  /// Turns the statement into its SQL text and parameters.
+  pub fn into_parts(self) -> (String, Vec<Value>) {
+    (self.sql, self.params)
+  }
+}
+
+impl Dialect {
+  /// Quotes a table or column name so it may be used in SQL.
+  pub fn quote(&self, identifier: &str) -> String {
+    match *self {
+      Dialect::MySql => format!("`{}`", identifier.replace("`", "``")),
+      Dialect::Postgres | Dialect::Sqlite => format!("\"{}\"", identifier.replace("\"", "\"\""))
+    }
+  }
+
+  /// Creates a statement reading the values of a table. Only the top level
+  /// properties of a `Query` are selected as columns, nested properties
+  /// should be selected with `Query::select` after reading.
+  pub fn select(&self, table: &str, condition: Condition, sort: Vec<SortRule>, range: Range, query: &Query) -> Result<Statement, Error> {
+    let mut writer = Writer::new(*self);
+    writer.push("SELECT ");
+    match *query {
+      Query::Keys(ref keys) if !keys.is_empty() => {
+        let columns = keys.keys().map(|key| self.quote(key)).collect::<Vec<_>>();
+        writer.push(&columns.join(", "));
+      },
+      _ => writer.push("*")
+    }
+    writer.push(" FROM ");
+    writer.push(&self.quote(table));
+    writer.push(" WHERE ");
+    try!(writer.condition(None, condition));
+    writer.order_by(sort);
+    writer.range(range);
+    Ok(writer.into_statement())
+  }
+
+  /// Creates a statement counting the values of a table which pass a
+  /// condition.
+  pub fn count(&self, table: &str, condition: Condition) -> Result<Statement, Error> {
+    let mut writer = Writer::new(*self);
+    writer.push("SELECT COUNT(*) FROM ");
+    writer.push(&self.quote(table));
+    writer.push(" WHERE ");
+    try!(writer.condition(None, condition));
+    Ok(writer.into_statement())
+  }
+
+  /// Creates a statement inserting an object into a table.
+  pub fn insert(&self, table: &str, object: Object) -> Statement {
+    let mut writer = Writer::new(*self);
+    writer.push("INSERT INTO ");
+    writer.push(&self.quote(table));
+    if object.is_empty() {
+      writer.push(match *self {
+        Dialect::MySql => " () VALUES ()",
+        Dialect::Postgres | Dialect::Sqlite => " DEFAULT VALUES"
+      });
+    } else {
+      let columns = object.keys().map(|key| self.quote(key)).collect::<Vec<_>>();
+      writer.push(&format!(" ({}) VALUES (", columns.join(", ")));
+      for (i, value) in object.into_iter().map(|(_, value)| value).enumerate() {
+        if i > 0 { writer.push(", "); }
+        writer.param(value);
+      }
+      writer.push(")");
+    }
+    writer.into_statement()
+  }
+
+  /// Creates a statement replacing the properties of the patch in every
+  /// value of a table which passes the condition. The patch must not be
+  /// empty.
+  pub fn update(&self, table: &str, condition: Condition, patch: Object) -> Result<Statement, Error> {
+    if patch.is_empty() {
+      return Err(Error::invalid(
+        "Cannot update with an empty patch in SQL.",
+        "Try counting the values instead, an empty patch changes nothing."
+      ));
+    }
+    let mut writer = Writer::new(*self);
+    writer.push("UPDATE ");
+    writer.push(&self.quote(table));
+    writer.push(" SET ");
+    for (i, (key, value)) in patch.into_iter().enumerate() {
+      if i > 0 { writer.push(", "); }
+      writer.push(&self.quote(&key));
+      writer.push(" = ");
+      writer.param(value);
+    }
+    writer.push(" WHERE ");
+    try!(writer.condition(None, condition));
+    Ok(writer.into_statement())
+  }
+
+  /// Creates a statement deleting every value of a table which passes the
+  /// condition.
+  pub fn delete(&self, table: &str, condition: Condition) -> Result<Statement, Error> {
+    let mut writer = Writer::new(*self);
+    writer.push("DELETE FROM ");
+    writer.push(&self.quote(table));
+    writer.push(" WHERE ");
+    try!(writer.condition(None, condition));
+    Ok(writer.into_statement())
+  }
+
+  /// Creates the boolean expression for a condition on a row, for use in a
+  /// `WHERE` clause. Only `Condition::Keys` and the boolean conditions may be
+  /// used on a row, all other conditions must be used on a property.
+  pub fn condition(&self, condition: Condition) -> Result<Statement, Error> {
+    let mut writer = Writer::new(*self);
+    try!(writer.condition(None, condition));
+    Ok(writer.into_statement())
+  }
+
+  /// Gets the SQL for true.
+  fn true_sql(&self) -> &'static str {
+    match *self {
+      Dialect::Sqlite => "1",
+      Dialect::Postgres | Dialect::MySql => "TRUE"
+    }
+  }
+
+  /// Gets the SQL for false.
+  fn false_sql(&self) -> &'static str {
+    match *self {
+      Dialect::Sqlite => "0",
+      Dialect::Postgres | Dialect::MySql => "FALSE"
+    }
+  }
+
+  /// Writes a string literal. MySQL also uses backslashes as escapes.
+  fn literal(&self, string: &str) -> String {
+    let string = string.replace("'", "''");
+    match *self {
+      Dialect::MySql => format!("'{}'", string.replace("\\", "\\\\")),
+      Dialect::Postgres | Dialect::Sqlite => format!("'{}'", string)
+    }
+  }
+}
+
+/// The kinds of values which the type of a property may be checked against.
+enum Kind {
+  Number,
+  String,
+  Array
+}
+
+/// A property of a row. Either a column, or a property nested in the JSON of
+/// a column.
+#[derive(Clone)]
+struct Property {
+  /// The name of the column.
+  column: Key,
+  /// The keys of the nested property in the column’s JSON.
+  path: Vec<Key>
+}
+
+impl Property {
+  fn new(column: Key) -> Self {
+    Property {
+      column: column,
+      path: Vec::new()
+    }
+  }
+
+  /// Gets the property nested in this one at a key.
+  fn push(&self, key: Key) -> Self {
+    let mut property = self.clone();
+    property.path.push(key);
+    property
+  }
+
+  /// Is the property nested in the JSON of a column?
+  fn is_json(&self) -> bool {
+    !self.path.is_empty()
+  }
+
+  /// Gets the path literal to the nested property. Integer keys are array
+  /// indexes in MySQL and SQLite.
+  fn path_sql(&self, dialect: Dialect) -> String {
+    match dialect {
+      Dialect::Postgres => {
+        let keys = self.path.iter().map(|key| format!("\"{}\"", key.replace("\\", "\\\\").replace("\"", "\\\""))).collect::<Vec<_>>();
+        dialect.literal(&format!("{{{}}}", keys.join(",")))
+      },
+      Dialect::MySql | Dialect::Sqlite => {
+        let mut path = "$".to_owned();
+        for key in &self.path {
+          if !key.is_empty() && key.chars().all(|c| c.is_digit(10)) {
+            path.push_str(&format!("[{}]", key));
+          } else {
+            path.push_str(&format!(".\"{}\"", key.replace("\\", "\\\\").replace("\"", "\\\"")));
+          }
+        }
+        dialect.literal(&path)
+      }
+    }
+  }
+
+  /// Gets the expression for the value of the property.
+  fn to_sql(&self, dialect: Dialect) -> String {
+    let column = dialect.quote(&self.column);
+    if !self.is_json() {
+      return column;
+    }
+    match dialect {
+      Dialect::Postgres => format!("({} #> {})", column, self.path_sql(dialect)),
+      Dialect::MySql => format!("JSON_EXTRACT({}, {})", column, self.path_sql(dialect)),
+      Dialect::Sqlite => format!("json_extract({}, {})", column, self.path_sql(dialect))
+    }
+  }
+
+  /// Gets the expression checking if the property is of a kind. If the
+  /// database already knows the kind because of the column type, `None` is
+  /// returned.
+  fn kind_sql(&self, dialect: Dialect, kind: Kind) -> Option<String> {
+    let expression = self.to_sql(dialect);
+    match (dialect, kind) {
+      (Dialect::Sqlite, Kind::Number) => Some(format!("typeof({}) IN ('integer', 'real')", expression)),
+      (Dialect::Sqlite, Kind::String) => Some(format!("typeof({}) = 'text'", expression)),
+      (Dialect::Sqlite, Kind::Array) => Some(format!("json_type({}) = 'array'", expression)),
+      (_, Kind::Number) | (_, Kind::String) if !self.is_json() => None,
+      (Dialect::MySql, Kind::Number) => Some(format!("JSON_TYPE({}) IN ('INTEGER', 'UNSIGNED INTEGER', 'DOUBLE', 'DECIMAL')", expression)),
+      (Dialect::MySql, Kind::String) => Some(format!("JSON_TYPE({}) = 'STRING'", expression)),
+      (Dialect::MySql, Kind::Array) => Some(format!("JSON_TYPE({}) = 'ARRAY'", expression)),
+      (Dialect::Postgres, Kind::Number) => Some(format!("jsonb_typeof({}) = 'number'", expression)),
+      (Dialect::Postgres, Kind::String) => Some(format!("jsonb_typeof({}) = 'string'", expression)),
+      (Dialect::Postgres, Kind::Array) => Some(format!("jsonb_typeof({}) = 'array'", expression))
+    }
+  }
+}
+
+/// Formats a value as JSON text for binding to a JSON parameter.
+fn json_text(value: Value) -> Value {
+  Value::String(value.to_json().unwrap_or_else(|_| "null".to_owned()))
+}
+
+/// Writes SQL and parameters together so that placeholders are numbered in
+/// the order they are written.
+struct Writer {
+  dialect: Dialect,
+  sql: String,
+  params: Vec<Value>
+}
+
+impl Writer {
+  fn new(dialect: Dialect) -> Self {
+    Writer {
+      dialect: dialect,
+      sql: String::new(),
+      params: Vec::new()
+    }
+  }
+
+  fn into_statement(self) -> Statement {
+    Statement {
+      sql: self.sql,
+      params: self.params
+    }
+  }
+
+  /// Writes some SQL.
+  fn push(&mut self, sql: &str) {
+    self.sql.push_str(sql);
+  }
+
+  /// Writes a placeholder for a parameter.
+  fn param(&mut self, value: Value) {
+    self.params.push(value);
+    match self.dialect {
+      Dialect::Postgres => {
+        let placeholder = format!("${}", self.params.len());
+        self.push(&placeholder);
+      },
+      Dialect::MySql | Dialect::Sqlite => self.push("?")
+    }
+  }
+
+  /// Writes a placeholder for a parameter compared with a property. When the
+  /// property is JSON the parameter is cast to JSON, except in SQLite where
+  /// JSON values are extracted as SQL values.
+  fn operand(&mut self, property: &Property, value: Value) {
+    match self.dialect {
+      Dialect::Postgres if property.is_json() => {
+        self.param(json_text(value));
+        self.push("::jsonb");
+      },
+      Dialect::MySql if property.is_json() => {
+        self.push("CAST(");
+        self.param(json_text(value));
+        self.push(" AS JSON)");
+      },
+      _ => self.param(value)
+    }
+  }
+
+  /// Writes an expression which is true if the property is `null` or
+  /// missing.
+  fn is_null(&mut self, property: &Property) {
+    let expression = property.to_sql(self.dialect);
+    let sql = match self.dialect {
+      Dialect::Postgres if property.is_json() => format!("({} IS NULL OR jsonb_typeof({}) = 'null')", expression, expression),
+      Dialect::MySql if property.is_json() => format!("({} IS NULL OR JSON_TYPE({}) = 'NULL')", expression, expression),
+      _ => format!("{} IS NULL", expression)
+    };
+    self.push(&sql);
+  }
+
+  /// Writes some conditions joined by a SQL operator. With no conditions the
+  /// empty SQL is written instead.
+  fn join(&mut self, property: Option<&Property>, conditions: Vec<Condition>, operator: &str, empty: &str) -> Result<(), Error> {
+    if conditions.is_empty() {
+      self.push(empty);
+      return Ok(());
+    }
+    self.push("(");
+    for (i, condition) in conditions.into_iter().enumerate() {
+      if i > 0 { self.push(operator); }
+      try!(self.condition(property, condition));
+    }
+    self.push(")");
+    Ok(())
+  }
+
+  /// Writes the boolean expression for a condition. When the property is
+  /// `None` the condition is on the entire row.
+  fn condition(&mut self, property: Option<&Property>, condition: Condition) -> Result<(), Error> {
+    let dialect = self.dialect;
+    match condition {
+      Condition::True => self.push(dialect.true_sql()),
+      Condition::False => self.push(dialect.false_sql()),
+      // SQL comparisons with `NULL` are `NULL` instead of false, which `NOT`
+      // would keep as `NULL` instead of making true.
+      Condition::Not(condition) => {
+        self.push("NOT COALESCE(");
+        try!(self.condition(property, *condition));
+        self.push(", ");
+        self.push(dialect.false_sql());
+        self.push(")");
+      },
+      Condition::And(conditions) => try!(self.join(property, conditions, " AND ", dialect.true_sql())),
+      Condition::Or(conditions) => try!(self.join(property, conditions, " OR ", dialect.false_sql())),
+      Condition::Keys(keys) => {
+        let conditions = keys.into_iter().map(|(key, condition)| {
+          (property.map_or_else(|| Property::new(key.clone()), |property| property.push(key.clone())), condition)
+        }).collect::<Vec<_>>();
+        if conditions.is_empty() {
+          self.push(dialect.true_sql());
+        } else {
+          self.push("(");
+          for (i, (property, condition)) in conditions.into_iter().enumerate() {
+            if i > 0 { self.push(" AND "); }
+            try!(self.condition(Some(&property), condition));
+          }
+          self.push(")");
+        }
+      },
+      condition => match property {
+        Some(property) => self.operator(property, condition),
+        None => return Err(Error::unimplemented("Cannot use an operator on an entire row in SQL, only on the properties of a row."))
+      }
+    }
+    Ok(())
+  }
+
+  /// Writes the boolean expression for an operator condition on a property.
+  fn operator(&mut self, property: &Property, condition: Condition) {
+    let dialect = self.dialect;
+    let expression = property.to_sql(dialect);
+    match condition {
+      Condition::Equal(Value::Null) => self.is_null(property),
+      Condition::Equal(value) => {
+        self.push(&format!("{} = ", expression));
+        self.operand(property, value);
+      },
+      Condition::GreaterThan(value) => self.ordering(property, ">", value),
+      Condition::GreaterThanOrEqual(value) => self.ordering(property, ">=", value),
+      Condition::LessThan(value) => self.ordering(property, "<", value),
+      Condition::LessThanOrEqual(value) => self.ordering(property, "<=", value),
+      Condition::In(values) => self.in_(property, values),
+      Condition::NotIn(values) => {
+        self.push("NOT COALESCE(");
+        self.in_(property, values);
+        self.push(", ");
+        self.push(dialect.false_sql());
+        self.push(")");
+      },
+      Condition::Exists => {
+        let sql = match dialect {
+          Dialect::Sqlite if property.is_json() => format!("json_type({}, {}) IS NOT NULL", dialect.quote(&property.column), property.path_sql(dialect)),
+          Dialect::MySql if property.is_json() => format!("JSON_CONTAINS_PATH({}, 'one', {})", dialect.quote(&property.column), property.path_sql(dialect)),
+          // A JSON `null` is not a SQL `NULL` in PostgreSQL.
+          _ => format!("{} IS NOT NULL", expression)
+        };
+        self.push(&sql);
+      },
+      Condition::Matches(regex) => {
+        let pattern = Value::String(regex.as_str().to_owned());
+        match dialect {
+          Dialect::Sqlite => self.push(&format!("{} REGEXP ", expression)),
+          Dialect::MySql if property.is_json() => self.push(&format!("(JSON_TYPE({}) = 'STRING' AND JSON_UNQUOTE({}) REGEXP ", expression, expression)),
+          Dialect::MySql => self.push(&format!("{} REGEXP ", expression)),
+          Dialect::Postgres if property.is_json() => self.push(&format!("(jsonb_typeof({}) = 'string' AND ({} #>> '{{}}') ~ ", expression, expression)),
+          Dialect::Postgres => self.push(&format!("{} ~ ", expression))
+        }
+        self.param(pattern);
+        if property.is_json() && dialect != Dialect::Sqlite {
+          self.push(")");
+        }
+      },
+      Condition::Contains(value) => {
+        let kind = property.kind_sql(dialect, Kind::Array).unwrap_or_default();
+        match dialect {
+          // `json_each` fails on invalid JSON and iterates over a single
+          // scalar, so the property must be checked to be an array first.
+          Dialect::Sqlite => {
+            self.push(&format!(
+              "(CASE WHEN NOT COALESCE(json_valid({}), 0) THEN 0 WHEN {} THEN EXISTS (SELECT 1 FROM json_each({}) WHERE json_each.value = ",
+              expression,
+              kind,
+              expression
+            ));
+            self.param(value);
+            self.push(") ELSE 0 END)");
+          },
+          Dialect::MySql => {
+            self.push(&format!("({} AND JSON_CONTAINS({}, CAST(", kind, expression));
+            self.param(json_text(value));
+            self.push(" AS JSON)))");
+          },
+          Dialect::Postgres => {
+            self.push(&format!("({} AND {} @> ", kind, expression));
+            self.param(json_text(Value::Array(vec![value])));
+            self.push("::jsonb)");
+          }
+        }
+      },
+      Condition::True | Condition::False | Condition::Not(_) | Condition::And(_) | Condition::Or(_) | Condition::Keys(_) => unreachable!()
+    }
+  }
+
+  /// Writes an ordering comparison. Values of different types are never
+  /// greater or less than each other, so the property is checked to be of
+  /// the same kind as the value first.
+  fn ordering(&mut self, property: &Property, operator: &str, value: Value) {
+    let dialect = self.dialect;
+    let kind = match value {
+      Value::I64(_) | Value::F64(_) => Kind::Number,
+      Value::String(_) => Kind::String,
+      _ => return self.push(dialect.false_sql())
+    };
+    let expression = property.to_sql(dialect);
+    match property.kind_sql(dialect, kind) {
+      Some(kind) => {
+        self.push(&format!("({} AND {} {} ", kind, expression, operator));
+        self.operand(property, value);
+        self.push(")");
+      },
+      None => {
+        self.push(&format!("{} {} ", expression, operator));
+        self.operand(property, value);
+      }
+    }
+  }
+
+  /// Writes a `Condition::In`. SQL `IN` never matches `NULL`, so `null` is
+  /// checked for separately.
+  fn in_(&mut self, property: &Property, values: Vec<Value>) {
+    let dialect = self.dialect;
+    let has_null = values.contains(&Value::Null);
+    let values = values.into_iter().filter(|value| *value != Value::Null).collect::<Vec<_>>();
+    match (values.is_empty(), has_null) {
+      (true, false) => self.push(dialect.false_sql()),
+      (true, true) => self.is_null(property),
+      (false, _) => {
+        if has_null { self.push("("); }
+        self.push(&format!("{} IN (", property.to_sql(dialect)));
+        for (i, value) in values.into_iter().enumerate() {
+          if i > 0 { self.push(", "); }
+          self.operand(property, value);
+        }
+        self.push(")");
+        if has_null {
+          self.push(" OR ");
+          self.is_null(property);
+          self.push(")");
+        }
+      }
+    }
+  }
+
+  /// Writes an `ORDER BY` clause if there are any sort rules.
+  fn order_by(&mut self, sort: Vec<SortRule>) {
+    let dialect = self.dialect;
+    if sort.is_empty() {
+      return;
+    }
+    let expressions = sort.into_iter().map(|rule| {
+      let mut keys = rule.property().iter().cloned();
+      let mut property = Property::new(keys.next().unwrap_or_default());
+      for key in keys {
+        property = property.push(key);
+      }
+      format!("{} {}", property.to_sql(dialect), if rule.is_descending() { "DESC" } else { "ASC" })
+    }).collect::<Vec<_>>();
+    self.push(" ORDER BY ");
+    self.push(&expressions.join(", "));
+  }
+
+  /// Writes `LIMIT` and `OFFSET` clauses for a range. MySQL and SQLite need a
+  /// limit to use an offset, so the largest possible limit is used.
+  fn range(&mut self, range: Range) {
+    match self.dialect {
+      Dialect::Postgres => {
+        if let Some(limit) = range.limit() {
+          self.push(" LIMIT ");
+          self.param(Value::I64(limit as i64));
+        }
+        if let Some(skip) = range.skip() {
+          self.push(" OFFSET ");
+          self.param(Value::I64(skip as i64));
+        }
+      },
+      Dialect::MySql | Dialect::Sqlite => {
+        if range.limit().is_some() || range.skip().is_some() {
+          // A negative limit is no limit in SQLite.
+          let no_limit = if self.dialect == Dialect::Sqlite { -1 } else { i64::MAX };
+          self.push(" LIMIT ");
+          self.param(Value::I64(range.limit().map_or(no_limit, |limit| limit as i64)));
+          self.push(" OFFSET ");
+          self.param(Value::I64(range.skip().unwrap_or(0) as i64));
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use regex::Regex;
+
+  use query::{Condition, SortRule, Range, Query};
+  use query::sql::Dialect;
+  use value::Value;
+
+  fn get_condition() -> Condition {
+    Condition::Keys(linear_map! {
+      str!("a") => Condition::Equal(Value::I64(1)),
+      str!("b") => Condition::Keys(linear_map! {
+        str!("c") => Condition::Or(vec![
+          Condition::GreaterThan(Value::F64(2.5)),
+          Condition::Equal(Value::Null)
+        ]),
+        str!("0") => Condition::Exists
+      }),
+      str!("d") => Condition::Not(Box::new(Condition::In(vec![Value::Boolean(true), Value::Null]))),
+      str!("e") => Condition::Matches(Regex::new("^x").unwrap()),
+      str!("f") => Condition::Contains(Value::String(str!("y")))
+    })
+  }
+
+  fn get_sort() -> Vec<SortRule> {
+    vec![SortRule::new(point!["a"], true), SortRule::new(point!["b", "0", "c"], false)]
+  }
+
+  fn get_query() -> Query {
+    Query::Keys(linear_map! {
+      str!("a") => Query::All,
+      str!("b") => Query::Keys(linear_map! {
+        str!("c") => Query::All
+      })
+    })
+  }
+
+  #[test]
+  fn test_select_sqlite() {
+    let statement = Dialect::Sqlite.select("my\"table", get_condition(), get_sort(), Range::new(Some(5), None), &get_query()).unwrap();
+    assert_eq!(statement.sql(), concat!(
+      "SELECT \"a\", \"b\" FROM \"my\"\"table\" WHERE (",
+      "\"a\" = ? AND ",
+      "(((typeof(json_extract(\"b\", '$.\"c\"')) IN ('integer', 'real') AND json_extract(\"b\", '$.\"c\"') > ?) OR json_extract(\"b\", '$.\"c\"') IS NULL) AND ",
+      "json_type(\"b\", '$[0]') IS NOT NULL) AND ",
+      "NOT COALESCE((\"d\" IN (?) OR \"d\" IS NULL), 0) AND ",
+      "\"e\" REGEXP ? AND ",
+      "(CASE WHEN NOT COALESCE(json_valid(\"f\"), 0) THEN 0 WHEN json_type(\"f\") = 'array' THEN EXISTS (SELECT 1 FROM json_each(\"f\") WHERE json_each.value = ?) ELSE 0 END)",
+      ") ORDER BY \"a\" ASC, json_extract(\"b\", '$[0].\"c\"') DESC LIMIT ? OFFSET ?"
+    ));
+    assert_eq!(statement.params(), &vec![
+      Value::I64(1),
+      Value::F64(2.5),
+      Value::Boolean(true),
+      Value::String(str!("^x")),
+      Value::String(str!("y")),
+      Value::I64(-1),
+      Value::I64(5)
+    ]);
+  }
+
+  #[test]
+  fn test_select_mysql() {
+    let statement = Dialect::MySql.select("my`table", get_condition(), get_sort(), Range::new(Some(5), Some(10)), &Query::All).unwrap();
+    assert_eq!(statement.sql(), concat!(
+      "SELECT * FROM `my``table` WHERE (",
+      "`a` = ? AND ",
+      "(((JSON_TYPE(JSON_EXTRACT(`b`, '$.\"c\"')) IN ('INTEGER', 'UNSIGNED INTEGER', 'DOUBLE', 'DECIMAL') AND JSON_EXTRACT(`b`, '$.\"c\"') > CAST(? AS JSON)) OR ",
+      "(JSON_EXTRACT(`b`, '$.\"c\"') IS NULL OR JSON_TYPE(JSON_EXTRACT(`b`, '$.\"c\"')) = 'NULL')) AND ",
+      "JSON_CONTAINS_PATH(`b`, 'one', '$[0]')) AND ",
+      "NOT COALESCE((`d` IN (?) OR `d` IS NULL), FALSE) AND ",
+      "`e` REGEXP ? AND ",
+      "(JSON_TYPE(`f`) = 'ARRAY' AND JSON_CONTAINS(`f`, CAST(? AS JSON)))",
+      ") ORDER BY `a` ASC, JSON_EXTRACT(`b`, '$[0].\"c\"') DESC LIMIT ? OFFSET ?"
+    ));
+    assert_eq!(statement.params(), &vec![
+      Value::I64(1),
+      Value::String(str!("2.5")),
+      Value::Boolean(true),
+      Value::String(str!("^x")),
+      Value::String(str!("\"y\"")),
+      Value::I64(10),
+      Value::I64(5)
+    ]);
+  }
+
+  #[test]
+  fn test_select_postgres() {
+    let statement = Dialect::Postgres.select("my\"table", get_condition(), get_sort(), Range::new(None, Some(10)), &Query::All).unwrap();
+    assert_eq!(statement.sql(), concat!(
+      "SELECT * FROM \"my\"\"table\" WHERE (",
+      "\"a\" = $1 AND ",
+      "(((jsonb_typeof((\"b\" #> '{\"c\"}')) = 'number' AND (\"b\" #> '{\"c\"}') > $2::jsonb) OR ",
+      "((\"b\" #> '{\"c\"}') IS NULL OR jsonb_typeof((\"b\" #> '{\"c\"}')) = 'null')) AND ",
+      "(\"b\" #> '{\"0\"}') IS NOT NULL) AND ",
+      "NOT COALESCE((\"d\" IN ($3) OR \"d\" IS NULL), FALSE) AND ",
+      "\"e\" ~ $4 AND ",
+      "(jsonb_typeof(\"f\") = 'array' AND \"f\" @> $5::jsonb)",
+      ") ORDER BY \"a\" ASC, (\"b\" #> '{\"0\",\"c\"}') DESC LIMIT $6"
+    ));
+    assert_eq!(statement.params(), &vec![
+      Value::I64(1),
+      Value::String(str!("2.5")),
+      Value::Boolean(true),
+      Value::String(str!("^x")),
+      Value::String(str!("[\"y\"]")),
+      Value::I64(10)
+    ]);
+  }
+
+  #[test]
+  fn test_condition() {
+    assert_eq!(Dialect::Sqlite.condition(Condition::True).unwrap().sql(), "1");
+    assert_eq!(Dialect::Postgres.condition(Condition::And(vec![])).unwrap().sql(), "TRUE");
+    assert_eq!(Dialect::MySql.condition(Condition::Or(vec![])).unwrap().sql(), "FALSE");
+    assert_eq!(Dialect::Sqlite.condition(Condition::Keys(linear_map! {})).unwrap().sql(), "1");
+    assert_eq!(
+      Dialect::Postgres.condition(Condition::Keys(linear_map! {
+        str!("a") => Condition::And(vec![
+          Condition::LessThanOrEqual(Value::String(str!("z"))),
+          Condition::GreaterThan(Value::Boolean(true)),
+          Condition::In(vec![]),
+          Condition::NotIn(vec![Value::Null])
+        ]),
+        str!("b") => Condition::Keys(linear_map! {
+          str!("it's") => Condition::Matches(Regex::new("a").unwrap())
+        })
+      })).unwrap().sql(),
+      concat!(
+        "((\"a\" <= $1 AND FALSE AND FALSE AND NOT COALESCE(\"a\" IS NULL, FALSE)) AND ",
+        "(jsonb_typeof((\"b\" #> '{\"it''s\"}')) = 'string' AND ((\"b\" #> '{\"it''s\"}') #>> '{}') ~ $2))"
+      )
+    );
+    assert_eq!(
+      Dialect::MySql.condition(Condition::Keys(linear_map! {
+        str!("a") => Condition::Keys(linear_map! {
+          str!("b\\c") => Condition::Equal(Value::Boolean(false))
+        })
+      })).unwrap().sql(),
+      "(JSON_EXTRACT(`a`, '$.\"b\\\\\\\\c\"') = CAST(? AS JSON))"
+    );
+    Dialect::Sqlite.condition(Condition::Equal(Value::I64(1))).unwrap_err().expect("Cannot use an operator on an entire row");
+  }
+
+  #[test]
+  fn test_insert() {
+    assert_eq!(Dialect::Sqlite.insert("t", linear_map! {}).sql(), "INSERT INTO \"t\" DEFAULT VALUES");
+    assert_eq!(Dialect::MySql.insert("t", linear_map! {}).sql(), "INSERT INTO `t` () VALUES ()");
+    let statement = Dialect::Postgres.insert("t", linear_map! {
+      str!("a") => Value::I64(1),
+      str!("b") => value!({ "c" => true })
+    });
+    assert_eq!(statement.sql(), "INSERT INTO \"t\" (\"a\", \"b\") VALUES ($1, $2)");
+    assert_eq!(statement.params(), &vec![Value::I64(1), value!({ "c" => true })]);
+  }
+
+  #[test]
+  fn test_update() {
+    let statement = Dialect::Postgres.update("t", Condition::Keys(linear_map! {
+      str!("a") => Condition::Equal(Value::I64(1))
+    }), linear_map! {
+      str!("b") => Value::I64(2),
+      str!("c") => Value::Null
+    }).unwrap();
+    assert_eq!(statement.sql(), "UPDATE \"t\" SET \"b\" = $1, \"c\" = $2 WHERE (\"a\" = $3)");
+    assert_eq!(statement.params(), &vec![Value::I64(2), Value::Null, Value::I64(1)]);
+    Dialect::Sqlite.update("t", Condition::True, linear_map! {}).unwrap_err().expect("empty patch");
+  }
+
+  #[test]
+  fn test_count_and_delete() {
+    assert_eq!(Dialect::MySql.count("t", Condition::True).unwrap().sql(), "SELECT COUNT(*) FROM `t` WHERE TRUE");
+    assert_eq!(Dialect::Sqlite.delete("t", Condition::False).unwrap().sql(), "DELETE FROM \"t\" WHERE 0");
+  }
+}
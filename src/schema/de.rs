@@ -1,17 +1,21 @@
 use std::collections::BTreeMap;
 
+use linear_map::LinearMap;
 use regex::Regex;
 use serde::de::{Deserialize, Deserializer, Error as DeError, Visitor, MapVisitor};
 use serde::de::impls::IgnoredAny;
 use url::Url;
 
-use schema::{Definition, Type, DriverConfig, Schema, BoxedSchema};
+use schema::{Definition, Type, DriverConfig, Schema, BoxedSchema, SchemaDefault, SchemaEnum, SchemaObject, SchemaReference, SchemaString, StringFormat};
 use value::{Key, Value};
 
+/// Visits every field of a map into the variable of the same name. Keys which
+/// are not fields have their values ignored. Evaluates to every key of the
+/// map in order.
 macro_rules! visit_map_fields {
   ($visitor:expr, { $($field_name:expr => $var_name:ident),* }) => {{
     #[allow(non_camel_case_types)]
-    enum __Field { $($var_name,)* __Ignore }
+    enum __Field { $($var_name,)* __Ignore(String) }
 
     impl Deserialize for __Field {
       #[inline]
@@ -24,7 +28,7 @@ macro_rules! visit_map_fields {
           fn visit_str<E>(&mut self, value: &str) -> Result<Self::Value, E> where E: DeError {
             match value {
               $($field_name => Ok(__Field::$var_name),)*
-              _ => Ok(__Field::__Ignore)
+              _ => Ok(__Field::__Ignore(value.to_owned()))
             }
           }
         }
@@ -33,17 +37,60 @@ macro_rules! visit_map_fields {
       }
     }
 
+    let mut keys: Vec<String> = Vec::new();
+
     while let Some(key) = try!($visitor.visit_key()) {
       match key {
-        $(__Field::$var_name => { $var_name = try!($visitor.visit_value()); },)*
-        __Field::__Ignore => { try!($visitor.visit_value::<IgnoredAny>()); }
+        $(__Field::$var_name => {
+          $var_name = try!($visitor.visit_value());
+          keys.push($field_name.to_owned());
+        },)*
+        __Field::__Ignore(key) => {
+          try!($visitor.visit_value::<IgnoredAny>());
+          keys.push(key);
+        }
       }
     }
 
     try!($visitor.end());
+    keys
   }}
 }
 
+/// Keywords which only describe a schema, and so are allowed on every schema
+/// without being read.
+const META_KEYWORDS: &'static [&'static str] = &["title", "description"];
+
+/// The keywords of a custom type.
+const TYPE_KEYWORDS: &'static [&'static str] = &["driver", "key", "type", "properties", "required", "patternProperties", "additionalProperties"];
+/// The keywords of a `null` or `boolean` schema.
+const PLAIN_KEYWORDS: &'static [&'static str] = &["type", "default"];
+/// The keywords of a `number` or `integer` schema.
+const NUMBER_KEYWORDS: &'static [&'static str] = &["type", "multipleOf", "minimum", "exclusiveMinimum", "maximum", "exclusiveMaximum", "default"];
+/// The keywords of a `string` schema.
+const STRING_KEYWORDS: &'static [&'static str] = &["type", "minLength", "maxLength", "pattern", "format", "default"];
+/// The keywords of an `array` schema.
+const ARRAY_KEYWORDS: &'static [&'static str] = &["type", "items", "minItems", "maxItems", "default"];
+/// The keywords of an `object` schema.
+const OBJECT_KEYWORDS: &'static [&'static str] = &["type", "properties", "required", "patternProperties", "additionalProperties", "default"];
+/// The keywords of an `enum` schema.
+const ENUM_KEYWORDS: &'static [&'static str] = &["enum", "default"];
+/// The keywords of a `$type` reference schema.
+const REFERENCE_KEYWORDS: &'static [&'static str] = &["$type", "default"];
+/// The keywords of a `oneOf` or `anyOf` union schema.
+const UNION_KEYWORDS: &'static [&'static str] = &["oneOf", "anyOf", "discriminator", "default"];
+
+/// Fails on the first keyword which is neither a meta keyword nor allowed for
+/// the kind of schema, so that a typo like `maxItem` or a keyword of another
+/// kind like `minItems` on a string is not silently ignored. The kind is the
+/// keyword which picked the allowed keywords, like `type: string`.
+fn deny_unknown_keywords<E>(keywords: Vec<String>, allowed: &[&str], kind: &str) -> Result<(), E> where E: DeError {
+  match keywords.into_iter().find(|keyword| !META_KEYWORDS.contains(&keyword.as_str()) && !allowed.contains(&keyword.as_str())) {
+    Some(keyword) => Err(E::custom(format!("Cannot use the schema keyword '{}' with `{}`.", keyword, kind))),
+    None => Ok(())
+  }
+}
+
 impl Deserialize for Definition {
   fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error> where D: Deserializer {
     struct DefinitionVisitor;
@@ -71,6 +118,10 @@ impl Deserialize for Definition {
           }
         }
 
+        if let Err(error) = definition.resolve_references() {
+          return Err(DeError::custom(error.message()));
+        }
+
         Ok(definition)
       }
     }
@@ -89,18 +140,23 @@ impl Deserialize for Type {
       #[inline]
       fn visit_map<V>(&mut self, mut visitor: V) -> Result<Self::Value, V::Error> where V: MapVisitor {
         let mut driver_config: Option<DriverConfig> = None;
+        let mut key: Option<String> = None;
         let mut type_string: Option<String> = None;
         let mut properties: Option<BTreeMap<String, BoxedSchema>> = None;
         let mut required: Option<Vec<String>> = None;
-        let mut additional_properties: Option<bool> = None;
+        let mut pattern_properties: Option<LinearMap<String, BoxedSchema>> = None;
+        let mut additional_properties: Option<AdditionalProperties> = None;
 
-        visit_map_fields!(visitor, {
+        let keys = visit_map_fields!(visitor, {
           "driver" => driver_config,
+          "key" => key,
           "type" => type_string,
           "properties" => properties,
           "required" => required,
+          "patternProperties" => pattern_properties,
           "additionalProperties" => additional_properties
         });
+        try!(deny_unknown_keywords::<V::Error>(keys, TYPE_KEYWORDS, "type: object"));
 
         if let Some(type_string) = type_string {
           if type_string != "object" {
@@ -113,12 +169,19 @@ impl Deserialize for Type {
         let mut type_ = Type::new();
 
         type_.set_required(required.unwrap_or_default());
-        if additional_properties.unwrap_or(false) { type_.enable_additional_properties(); }
+        if let Some(AdditionalProperties(Some(schema))) = additional_properties { type_.set_boxed_additional_properties(schema); }
         for (key, schema) in properties.unwrap_or_default() {
           type_.add_boxed_property(key, schema);
         }
+        for (pattern, schema) in pattern_properties.unwrap_or_default() {
+          match Regex::new(&pattern) {
+            Ok(pattern) => type_.add_boxed_pattern_property(pattern, schema),
+            Err(_) => return Err(DeError::custom(format!("Cannot use invalid pattern '{}' for a pattern property.", pattern)))
+          }
+        }
 
         if let Some(driver_config) = driver_config { type_.set_driver(driver_config); }
+        if let Some(key) = key { type_.set_key(key); }
 
         Ok(type_)
       }
@@ -155,93 +218,222 @@ impl Deserialize for DriverConfig {
 
 impl Deserialize for BoxedSchema {
   fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error> where D: Deserializer {
-    struct SchemaVisitor;
-
-    impl Visitor for SchemaVisitor {
-      type Value = BoxedSchema;
-
-      #[inline]
-      fn visit_map<V>(&mut self, mut visitor: V) -> Result<Self::Value, V::Error> where V: MapVisitor {
-        let mut type_: Option<String> = None;
-        let mut multiple_of: Option<f32> = None;
-        let mut minimum: Option<f64> = None;
-        let mut exclusive_minimum: Option<bool> = None;
-        let mut maximum: Option<f64> = None;
-        let mut exclusive_maximum: Option<bool> = None;
-        let mut min_length: Option<u64> = None;
-        let mut max_length: Option<u64> = None;
-        let mut pattern: Option<String> = None;
-        let mut items: Option<BoxedSchema> = None;
-        let mut properties: Option<BTreeMap<String, BoxedSchema>> = None;
-        let mut required: Option<Vec<String>> = None;
-        let mut additional_properties: Option<bool> = None;
-        let mut enum_: Option<Vec<Value>> = None;
+    deserializer.deserialize_map(SchemaVisitor)
+  }
+}
 
-        visit_map_fields!(visitor, {
-          "type" => type_,
-          "multipleOf" => multiple_of,
-          "minimum" => minimum,
-          "exclusiveMinimum" => exclusive_minimum,
-          "maximum" => maximum,
-          "exclusiveMaximum" => exclusive_maximum,
-          "minLength" => min_length,
-          "maxLength" => max_length,
-          "pattern" => pattern,
-          "items" => items,
-          "properties" => properties,
-          "required" => required,
-          "additionalProperties" => additional_properties,
-          "enum" => enum_
-        });
+struct SchemaVisitor;
+
+impl Visitor for SchemaVisitor {
+  type Value = BoxedSchema;
+
+  #[inline]
+  fn visit_map<V>(&mut self, mut visitor: V) -> Result<Self::Value, V::Error> where V: MapVisitor {
+    let mut type_: Option<String> = None;
+    let mut multiple_of: Option<f64> = None;
+    let mut minimum: Option<f64> = None;
+    let mut exclusive_minimum: Option<bool> = None;
+    let mut maximum: Option<f64> = None;
+    let mut exclusive_maximum: Option<bool> = None;
+    let mut min_length: Option<u64> = None;
+    let mut max_length: Option<u64> = None;
+    let mut pattern: Option<String> = None;
+    let mut format: Option<String> = None;
+    let mut items: Option<BoxedSchema> = None;
+    let mut min_items: Option<u64> = None;
+    let mut max_items: Option<u64> = None;
+    let mut properties: Option<BTreeMap<String, BoxedSchema>> = None;
+    let mut required: Option<Vec<String>> = None;
+    let mut pattern_properties: Option<LinearMap<String, BoxedSchema>> = None;
+    let mut additional_properties: Option<AdditionalProperties> = None;
+    let mut enum_: Option<Vec<Value>> = None;
+    let mut type_reference: Option<String> = None;
+    let mut one_of: Option<Vec<BoxedSchema>> = None;
+    let mut any_of: Option<Vec<BoxedSchema>> = None;
+    let mut discriminator: Option<String> = None;
+    let mut default: Option<Value> = None;
+
+    let keys = visit_map_fields!(visitor, {
+      "type" => type_,
+      "multipleOf" => multiple_of,
+      "minimum" => minimum,
+      "exclusiveMinimum" => exclusive_minimum,
+      "maximum" => maximum,
+      "exclusiveMaximum" => exclusive_maximum,
+      "minLength" => min_length,
+      "maxLength" => max_length,
+      "pattern" => pattern,
+      "format" => format,
+      "items" => items,
+      "minItems" => min_items,
+      "maxItems" => max_items,
+      "properties" => properties,
+      "required" => required,
+      "patternProperties" => pattern_properties,
+      "additionalProperties" => additional_properties,
+      "enum" => enum_,
+      "$type" => type_reference,
+      "oneOf" => one_of,
+      "anyOf" => any_of,
+      "discriminator" => discriminator,
+      "default" => default
+    });
+    // The keywords which pick the kind of schema are checked in the same order
+    // the kind is picked below. An unknown `type` is an error of its own.
+    let kind = if enum_.is_some() {
+      Some((ENUM_KEYWORDS, "enum".to_owned()))
+    } else if type_reference.is_some() {
+      Some((REFERENCE_KEYWORDS, "$type".to_owned()))
+    } else if one_of.is_some() || any_of.is_some() {
+      Some((UNION_KEYWORDS, if one_of.is_some() { "oneOf" } else { "anyOf" }.to_owned()))
+    } else if let Some(ref type_) = type_ {
+      match type_.as_str() {
+        "null" | "boolean" => Some(PLAIN_KEYWORDS),
+        "number" | "integer" => Some(NUMBER_KEYWORDS),
+        "string" => Some(STRING_KEYWORDS),
+        "array" => Some(ARRAY_KEYWORDS),
+        "object" => Some(OBJECT_KEYWORDS),
+        _ => None
+      }.map(|allowed| (allowed, format!("type: {}", type_)))
+    } else {
+      None
+    };
+    if let Some((allowed, kind)) = kind {
+      try!(deny_unknown_keywords::<V::Error>(keys, allowed, &kind));
+    }
 
-        if let Some(enum_) = enum_ {
-          return Ok(Box::new(Schema::enum_(enum_)));
+    let mut schema: BoxedSchema = if let Some(enum_) = enum_ {
+      Box::new(Schema::enum_(enum_))
+    } else if let Some(type_reference) = type_reference {
+      Box::new(Schema::reference(type_reference))
+    } else if let Some(variants) = one_of.or(any_of) {
+      // Variants are picked by their discriminant, so at most one variant
+      // ever matches and `oneOf` and `anyOf` are the same.
+      let discriminator = match discriminator {
+        Some(discriminator) => discriminator,
+        None => return Err(DeError::custom("A `oneOf` or `anyOf` schema must have a `discriminator` property."))
+      };
+      let mut schema = Schema::union(discriminator.clone());
+      for variant in variants {
+        let variant = match variant.downcast_ref::<SchemaObject>() {
+          Some(variant) => variant.clone(),
+          None => return Err(DeError::custom("Every `oneOf` or `anyOf` variant must be an object schema."))
+        };
+        let discriminant = match variant.properties().get(&discriminator).and_then(|property| property.downcast_ref::<SchemaEnum>()) {
+          Some(enum_) if enum_.values().len() == 1 => enum_.values()[0].clone(),
+          _ => return Err(DeError::custom(format!("Every `oneOf` or `anyOf` variant must have a \"{}\" property with a single `enum` value.", discriminator)))
+        };
+        if schema.variant(&discriminant).is_some() {
+          return Err(DeError::custom(format!("More than one variant has the \"{}\" value {:?}.", discriminator, discriminant)));
         }
-
-        if let Some(type_) = type_ {
-          match type_.as_str() {
-            "null" => Ok(Box::new(Schema::null())),
-            "boolean" => Ok(Box::new(Schema::boolean())),
-            "number" | "integer" => {
-              let mut schema = Schema::number();
-              if type_ == "integer" { schema.set_multiple_of(1.0); }
-              else if let Some(multiple_of) = multiple_of { schema.set_multiple_of(multiple_of); }
-              if let Some(minimum) = minimum { schema.set_minimum(minimum); }
-              if let Some(maximum) = maximum { schema.set_maximum(maximum); }
-              if exclusive_minimum.unwrap_or(false) { schema.enable_exclusive_minimum(); }
-              if exclusive_maximum.unwrap_or(false) { schema.enable_exclusive_maximum(); }
-              Ok(Box::new(schema))
-            },
-            "string" => {
-              let mut schema = Schema::string();
-              if let Some(min_length) = min_length { schema.set_min_length(min_length); }
-              if let Some(max_length) = max_length { schema.set_max_length(max_length); }
-              if let Some(pattern) = pattern.and_then(|p| Regex::new(&p).ok()) { schema.set_pattern(pattern); }
-              Ok(Box::new(schema))
-            },
-            "array" => {
-              let mut schema = Schema::array();
-              if let Some(items) = items { schema.set_boxed_items(items); }
-              Ok(Box::new(schema))
-            },
-            "object" => {
-              let mut schema = Schema::object();
-              schema.set_required(required.unwrap_or_default());
-              if additional_properties.unwrap_or(false) { schema.enable_additional_properties(); }
-              for (key, sub_schema) in properties.unwrap_or_default() {
-                schema.add_boxed_property(key, sub_schema);
-              }
-              Ok(Box::new(schema))
-            },
-            _ => Err(DeError::custom(format!("Cannot use '{}' for a schema type property.", type_)))
+        schema.add_variant(discriminant, variant);
+      }
+      Box::new(schema)
+    } else if let Some(type_) = type_ {
+      match type_.as_str() {
+        "null" => Box::new(Schema::null()),
+        "boolean" => Box::new(Schema::boolean()),
+        "number" | "integer" => {
+          let mut schema = if type_ == "integer" { Schema::integer() } else { Schema::number() };
+          if let Some(multiple_of) = multiple_of { schema.set_multiple_of(multiple_of); }
+          if let Some(minimum) = minimum { schema.set_minimum(minimum); }
+          if let Some(maximum) = maximum { schema.set_maximum(maximum); }
+          if exclusive_minimum.unwrap_or(false) { schema.enable_exclusive_minimum(); }
+          if exclusive_maximum.unwrap_or(false) { schema.enable_exclusive_maximum(); }
+          Box::new(schema)
+        },
+        "string" => {
+          let mut schema = Schema::string();
+          if let Some(min_length) = min_length { schema.set_min_length(min_length); }
+          if let Some(max_length) = max_length { schema.set_max_length(max_length); }
+          if let Some(pattern) = pattern.and_then(|p| Regex::new(&p).ok()) { schema.set_pattern(pattern); }
+          if let Some(format) = format {
+            match StringFormat::from_name(&format) {
+              Some(format) => schema.set_format(format),
+              None => return Err(DeError::custom(format!("Cannot use unknown string format '{}'.", format)))
+            }
+          }
+          Box::new(schema)
+        },
+        "array" => {
+          let mut schema = Schema::array();
+          if let Some(items) = items { schema.set_boxed_items(items); }
+          if let Some(min_items) = min_items { schema.set_min_items(min_items); }
+          if let Some(max_items) = max_items { schema.set_max_items(max_items); }
+          Box::new(schema)
+        },
+        "object" => {
+          let mut schema = Schema::object();
+          schema.set_required(required.unwrap_or_default());
+          if let Some(AdditionalProperties(Some(sub_schema))) = additional_properties { schema.set_boxed_additional_properties(sub_schema); }
+          for (key, sub_schema) in properties.unwrap_or_default() {
+            schema.add_boxed_property(key, sub_schema);
+          }
+          for (pattern, sub_schema) in pattern_properties.unwrap_or_default() {
+            match Regex::new(&pattern) {
+              Ok(pattern) => schema.add_boxed_pattern_property(pattern, sub_schema),
+              Err(_) => return Err(DeError::custom(format!("Cannot use invalid pattern '{}' for a pattern property.", pattern)))
+            }
+          }
+          Box::new(schema)
+        },
+        _ => return Err(DeError::custom(format!("Cannot use '{}' for a schema type property.", type_)))
+      }
+    } else {
+      return Err(DeError::custom("No type property for schema was specified."));
+    };
+
+    if let Some(default) = default {
+      let default = match SchemaDefault::from_value(default) {
+        Ok(default) => default,
+        Err(error) => return Err(DeError::custom(error.message()))
+      };
+      match default {
+        // A reference is not resolved until every type is loaded, so its
+        // default cannot be validated yet.
+        SchemaDefault::Value(_) if schema.downcast_ref::<SchemaReference>().is_some() => {},
+        SchemaDefault::Value(ref value) => {
+          if let Err(error) = schema.validate_value(value) {
+            return Err(DeError::custom(format!("Default value is not valid for its schema. {}", error.message())));
+          }
+        },
+        SchemaDefault::Generated(ref generator) => {
+          if schema.downcast_ref::<SchemaString>().is_none() {
+            return Err(DeError::custom(format!("Cannot generate a '{}' default for a schema which is not a string.", generator.name())));
           }
-        } else {
-          Err(DeError::custom("No type property for schema was specified."))
         }
       }
+      schema.set_default(default);
     }
 
-    deserializer.deserialize_map(SchemaVisitor)
+    Ok(schema)
+  }
+}
+
+/// The value of an `additionalProperties` property, which is either whether
+/// any additional property is allowed or the schema of every additional
+/// property.
+struct AdditionalProperties(Option<BoxedSchema>);
+
+impl Deserialize for AdditionalProperties {
+  fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error> where D: Deserializer {
+    struct AdditionalPropertiesVisitor;
+
+    impl Visitor for AdditionalPropertiesVisitor {
+      type Value = AdditionalProperties;
+
+      #[inline]
+      fn visit_bool<E>(&mut self, value: bool) -> Result<Self::Value, E> where E: DeError {
+        Ok(AdditionalProperties(if value { Some(Box::new(Schema::none()) as BoxedSchema) } else { None }))
+      }
+
+      #[inline]
+      fn visit_map<V>(&mut self, visitor: V) -> Result<Self::Value, V::Error> where V: MapVisitor {
+        SchemaVisitor.visit_map(visitor).map(|schema| AdditionalProperties(Some(schema)))
+      }
+    }
+
+    deserializer.deserialize(AdditionalPropertiesVisitor)
   }
 }
 
@@ -250,7 +442,7 @@ mod tests {
   use serde_json;
   use url::Url;
 
-  use schema::{Definition, Type, DriverConfig};
+  use schema::{Definition, Type, DriverConfig, BoxedSchema, SchemaDefault, Generator, SchemaNumber, SchemaObject, SchemaString, SchemaUnion, StringFormat};
 
   #[test]
   fn test_json_definition() {
@@ -269,12 +461,100 @@ mod tests {
     assert_eq!(from_str(r#"{"type":"object"}"#).unwrap(), Type::new());
     assert!(from_str("{}").is_err());
     assert!(from_str(r#"{"hello":"world"}"#).is_err());
-    assert_eq!(from_str(r#"{"type":"object","hello":"world"}"#).unwrap(), Type::new());
+    assert_eq!(from_str(r#"{"type":"object","title":"Hello","description":"World"}"#).unwrap(), Type::new());
+    assert!(from_str(r#"{"type":"object","hello":"world"}"#).is_err());
     assert!(from_str(r#"{"type":2}"#).is_err());
     assert!(from_str(r#"{"type":"yo"}"#).is_err());
     assert!(from_str(r#"{"type":[]}"#).is_err());
   }
 
+  #[test]
+  fn test_json_schema_unknown_keyword() {
+    let from_str = serde_json::from_str::<BoxedSchema>;
+    assert!(from_str(r#"{"type":"array","title":"Tags","description":"Some tags.","maxItems":4}"#).is_ok());
+    assert!(from_str(r#"{"type":"array","maxItem":4}"#).is_err());
+    assert!(from_str(r#"{"type":"array","minitems":1}"#).is_err());
+    assert!(from_str(r#"{"type":"object","properties":{"a":{"type":"string","maxlength":4}}}"#).is_err());
+  }
+
+  #[test]
+  fn test_json_schema_wrong_type_keyword() {
+    let from_str = serde_json::from_str::<BoxedSchema>;
+    assert!(from_str(r#"{"type":"string","minLength":1,"default":"a"}"#).is_ok());
+    assert!(from_str(r#"{"type":"string","minItems":1}"#).is_err());
+    assert!(from_str(r#"{"type":"array","maxLength":4}"#).is_err());
+    assert!(from_str(r#"{"type":"array","format":"email"}"#).is_err());
+    assert!(from_str(r#"{"type":"integer","pattern":"^a"}"#).is_err());
+    assert!(from_str(r#"{"type":"boolean","minimum":1}"#).is_err());
+    assert!(from_str(r#"{"type":"object","items":{"type":"string"}}"#).is_err());
+    assert!(from_str(r#"{"type":"string","enum":["a"]}"#).is_err());
+    assert!(from_str(r#"{"type":"object","$type":"person"}"#).is_err());
+    assert!(from_str(r#"{"$type":"person","properties":{}}"#).is_err());
+    assert!(from_str(r#"{"discriminator":"kind","type":"object","oneOf":[{"type":"object","properties":{"kind":{"enum":["a"]}}}]}"#).is_err());
+    assert!(serde_json::from_str::<Type>(r#"{"type":"object","default":{}}"#).is_err());
+  }
+
+  #[test]
+  fn test_json_schema_default() {
+    let from_str = serde_json::from_str::<BoxedSchema>;
+    assert_eq!(from_str(r#"{"type":"string"}"#).unwrap().default(), None);
+    assert_eq!(from_str(r#"{"type":"number","default":42}"#).unwrap().default(), Some(&SchemaDefault::Value(value!(42))));
+    assert_eq!(from_str(r#"{"enum":["a","b"],"default":"a"}"#).unwrap().default(), Some(&SchemaDefault::Value(value!("a"))));
+    assert_eq!(from_str(r#"{"type":"string","default":{"$gen":"uuid"}}"#).unwrap().default(), Some(&SchemaDefault::Generated(Generator::Uuid)));
+    assert!(from_str(r#"{"type":"string","default":{"$gen":"party"}}"#).is_err());
+    assert!(from_str(r#"{"type":"number","default":"abc"}"#).is_err());
+    assert!(from_str(r#"{"type":"string","minLength":4,"default":"abc"}"#).is_err());
+    assert!(from_str(r#"{"enum":["a","b"],"default":"c"}"#).is_err());
+    assert!(from_str(r#"{"type":"number","default":{"$gen":"uuid"}}"#).is_err());
+    assert!(from_str(r#"{"$type":"post","default":{"$gen":"timestamp"}}"#).is_err());
+  }
+
+  #[test]
+  fn test_json_schema_format() {
+    let from_str = serde_json::from_str::<BoxedSchema>;
+    assert_eq!(from_str(r#"{"type":"string","format":"date-time"}"#).unwrap().downcast_ref::<SchemaString>().unwrap().format(), Some(StringFormat::DateTime));
+    assert_eq!(from_str(r#"{"type":"string"}"#).unwrap().downcast_ref::<SchemaString>().unwrap().format(), None);
+    assert!(from_str(r#"{"type":"string","format":"party"}"#).is_err());
+  }
+
+  #[test]
+  fn test_json_schema_union() {
+    let from_str = serde_json::from_str::<BoxedSchema>;
+    let schema = from_str(r#"{"discriminator":"kind","oneOf":[
+      {"type":"object","properties":{"kind":{"enum":["a"]},"x":{"type":"number"}}},
+      {"type":"object","properties":{"kind":{"enum":["b"]}}}
+    ]}"#).unwrap();
+    let union = schema.downcast_ref::<SchemaUnion>().unwrap();
+    assert_eq!(union.discriminator(), "kind");
+    assert_eq!(union.variants().len(), 2);
+    assert!(union.variant(&value!("a")).unwrap().properties().contains_key("x"));
+    assert!(from_str(r#"{"anyOf":[{"type":"object","properties":{"kind":{"enum":["a"]}}}],"discriminator":"kind"}"#).is_ok());
+    assert!(from_str(r#"{"oneOf":[{"type":"object","properties":{"kind":{"enum":["a"]}}}]}"#).is_err());
+    assert!(from_str(r#"{"oneOf":[{"type":"string"}],"discriminator":"kind"}"#).is_err());
+    assert!(from_str(r#"{"oneOf":[{"type":"object","properties":{"kind":{"enum":["a","b"]}}}],"discriminator":"kind"}"#).is_err());
+    assert!(from_str(r#"{"oneOf":[{"type":"object","properties":{"kind":{"enum":["a"]}}},{"type":"object","properties":{"kind":{"enum":["a"]}}}],"discriminator":"kind"}"#).is_err());
+  }
+
+  #[test]
+  fn test_json_schema_map() {
+    let from_str = serde_json::from_str::<BoxedSchema>;
+    let schema = from_str(r#"{"type":"object","patternProperties":{"^[a-z]{2}$":{"type":"string"}},"additionalProperties":{"type":"number"}}"#).unwrap();
+    assert!(schema.get(point!["en"]).unwrap().downcast_ref::<SchemaString>().is_some());
+    assert!(schema.get(point!["total"]).unwrap().downcast_ref::<SchemaNumber>().is_some());
+    assert!(from_str(r#"{"type":"object","additionalProperties":false}"#).unwrap().get(point!["total"]).is_none());
+    assert!(from_str(r#"{"type":"object","additionalProperties":true}"#).unwrap().get(point!["total"]).is_some());
+    assert!(from_str(r#"{"type":"object","additionalProperties":2}"#).is_err());
+    assert!(from_str(r#"{"type":"object","patternProperties":{"(":{"type":"string"}}}"#).is_err());
+    // Pattern properties keep the order they are written in.
+    let schema = from_str(r#"{"type":"object","patternProperties":{"^z":{"type":"string"},"^a":{"type":"number"}}}"#).unwrap();
+    let patterns: Vec<&str> = schema.downcast_ref::<SchemaObject>().unwrap().pattern_properties().into_iter().map(|(pattern, _)| pattern.as_str()).collect();
+    assert_eq!(patterns, vec!["^z", "^a"]);
+    let type_ = serde_json::from_str::<Type>(r#"{"type":"object","patternProperties":{"^x-":{"type":"string"}}}"#).unwrap();
+    assert_eq!(type_.pattern_properties().len(), 1);
+    assert!(type_.get_property("x-custom").is_some());
+    assert!(type_.additional_properties().is_none());
+  }
+
   #[test]
   fn test_json_driver_config() {
     let from_str = serde_json::from_str::<DriverConfig>;
@@ -0,0 +1,124 @@
+//! Default values used in place of a missing value, either given as is in
+//! the schema or generated with `$gen` every time a default is needed.
+
+use chrono::UTC;
+use uuid::Uuid;
+
+use error::Error;
+use value::Value;
+
+/// The value a schema uses when no value is provided.
+#[derive(Clone, PartialEq, Debug)]
+pub enum SchemaDefault {
+  /// A value which is used as is.
+  Value(Value),
+  /// A value which is generated every time it is used, like
+  /// `{ $gen: uuid }`.
+  Generated(Generator)
+}
+
+impl SchemaDefault {
+  /// Creates a default from the value of a `default` schema property. An
+  /// object with a single `$gen` property is a generated default, and any
+  /// other value is used as is.
+  pub fn from_value(value: Value) -> Result<Self, Error> {
+    let name = match value {
+      Value::Object(ref object) if object.len() == 1 && object.contains_key("$gen") => match object.get("$gen") {
+        Some(&Value::String(ref name)) => name.to_owned(),
+        _ => return Err(Error::invalid("The `$gen` property of a default must be a string.", "Try using a generator name like `uuid`."))
+      },
+      _ => return Ok(SchemaDefault::Value(value))
+    };
+    Generator::from_name(&name).map(SchemaDefault::Generated).ok_or_else(|| Error::invalid(
+      format!("Cannot generate a default with unknown generator '{}'.", name),
+      "Try using one of the generators: uuid, timestamp."
+    ))
+  }
+
+  /// Gets the value of the default, generating a new one if the default is
+  /// generated.
+  pub fn value(&self) -> Value {
+    match *self {
+      SchemaDefault::Value(ref value) => value.clone(),
+      SchemaDefault::Generated(ref generator) => generator.generate()
+    }
+  }
+}
+
+/// Generates new default values.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Generator {
+  /// Generates a random version 4 UUID in compliance with [RFC 4122][1], as
+  /// a hyphenated string.
+  ///
+  /// [1]: https://www.ietf.org/rfc/rfc4122.txt
+  Uuid,
+  /// Generates the current time in UTC as an [RFC 3339][1] string.
+  ///
+  /// [1]: https://tools.ietf.org/html/rfc3339
+  Timestamp
+}
+
+impl Generator {
+  /// Gets a generator by the name used for it with `$gen`.
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "uuid" => Some(Generator::Uuid),
+      "timestamp" => Some(Generator::Timestamp),
+      _ => None
+    }
+  }
+
+  /// Gets the name used for the generator with `$gen`.
+  pub fn name(&self) -> &'static str {
+    match *self {
+      Generator::Uuid => "uuid",
+      Generator::Timestamp => "timestamp"
+    }
+  }
+
+  /// Generates a new value.
+  pub fn generate(&self) -> Value {
+    match *self {
+      Generator::Uuid => Value::String(Uuid::new_v4().to_hyphenated_string()),
+      Generator::Timestamp => Value::String(UTC::now().to_rfc3339())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::DateTime;
+  use regex::Regex;
+
+  use schema::{SchemaDefault, Generator};
+  use value::Value;
+
+  #[test]
+  fn test_from_value() {
+    assert_eq!(SchemaDefault::from_value(value!("hello")).unwrap(), SchemaDefault::Value(value!("hello")));
+    assert_eq!(SchemaDefault::from_value(value!({ "a" => 1 })).unwrap(), SchemaDefault::Value(value!({ "a" => 1 })));
+    assert_eq!(SchemaDefault::from_value(value!({ "$gen" => "uuid" })).unwrap(), SchemaDefault::Generated(Generator::Uuid));
+    assert_eq!(SchemaDefault::from_value(value!({ "$gen" => "timestamp" })).unwrap(), SchemaDefault::Generated(Generator::Timestamp));
+    SchemaDefault::from_value(value!({ "$gen" => "party" })).unwrap_err().expect("unknown generator 'party'");
+    SchemaDefault::from_value(value!({ "$gen" => 5 })).unwrap_err().expect("must be a string");
+  }
+
+  #[test]
+  fn test_generate() {
+    let uuid_re = Regex::new(r"^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$").unwrap();
+    match (Generator::Uuid.generate(), Generator::Uuid.generate()) {
+      (Value::String(a), Value::String(b)) => {
+        assert!(uuid_re.is_match(&a));
+        assert!(uuid_re.is_match(&b));
+        assert!(a != b);
+      },
+      values => panic!("Expected strings, not {:?}.", values)
+    }
+    match Generator::Timestamp.generate() {
+      Value::String(timestamp) => assert!(DateTime::parse_from_rfc3339(&timestamp).is_ok()),
+      value => panic!("Expected a string, not {:?}.", value)
+    }
+    assert_eq!(SchemaDefault::Value(value!(42)).value(), value!(42));
+  }
+}
@@ -6,13 +6,15 @@ use std::fs::File;
 use std::path::PathBuf;
 
 use linear_map::LinearMap;
+use regex::Regex;
 use serde_json;
 use serde_yaml;
 use url::Url;
 
 use error::{Error, NotAcceptable};
+use query::{Condition, SortRule, Query};
 use schema::{Schema, SchemaObject, BoxedSchema};
-use value::Key;
+use value::{Key, Value};
 
 /// The definition object which contains all necessary information to
 /// understand an Ardite Schema Definition.
@@ -43,9 +45,17 @@ impl Definition {
     self.driver.as_ref()
   }
 
-  /// Add a new type to the `Definition`.
-  pub fn add_type<K>(&mut self, name: K, type_: Type) where K: Into<Key> {
-    self.types.insert(name.into(), type_);
+  /// Add a new type to the `Definition`. The type will be given the name it
+  /// is added under.
+  pub fn add_type<K>(&mut self, name: K, mut type_: Type) where K: Into<Key> {
+    let name = name.into();
+    type_.set_name(name.clone());
+    self.types.insert(name, type_);
+  }
+
+  /// Gets all of the types in the definition by name.
+  pub fn types(&self) -> &BTreeMap<Key, Type> {
+    &self.types
   }
 
   /// Gets type of a certain name.
@@ -53,9 +63,59 @@ impl Definition {
     self.types.get(name.into())
   }
 
+  /// Resolves every `$type` reference in the schemas of the types with the
+  /// schema of the type it references. Must be called again after types are
+  /// added, definitions read from a file are resolved automatically.
+  ///
+  /// Fails if a type references a type which is not defined, or if types
+  /// reference each other in a cycle.
+  pub fn resolve_references(&mut self) -> Result<(), Error> {
+    let mut order = Vec::new();
+    for name in self.types.keys() {
+      try!(self.order_references(name, &mut Vec::new(), &mut order));
+    }
+    // Types are resolved after the types they reference, so every referenced
+    // schema is already resolved when it is copied.
+    for name in order {
+      let mut type_ = self.types.remove(&name).unwrap();
+      type_.schema.resolve_references(self);
+      self.types.insert(name, type_);
+    }
+    Ok(())
+  }
+
+  /// Adds a type name to the order after every type it references. The path
+  /// is the chain of references which led to the type.
+  fn order_references(&self, name: &Key, path: &mut Vec<Key>, order: &mut Vec<Key>) -> Result<(), Error> {
+    if order.contains(name) {
+      return Ok(());
+    }
+    if let Some(i) = path.iter().position(|key| key == name) {
+      let mut cycle = path[i..].to_vec();
+      cycle.push(name.to_owned());
+      return Err(Error::invalid(
+        format!("Types reference each other in a cycle: {}.", cycle.join(" -> ")),
+        "Try removing one of the `$type` references in the cycle."
+      ));
+    }
+    path.push(name.to_owned());
+    for reference in self.types[name].schema.references() {
+      if !self.types.contains_key(reference) {
+        return Err(Error::invalid(
+          format!("Type '{}' references type '{}' which is not defined.", name, reference),
+          "Try referencing a type which is defined in `types`."
+        ));
+      }
+      try!(self.order_references(&reference.to_owned(), path, order));
+    }
+    path.pop();
+    order.push(name.to_owned());
+    Ok(())
+  }
+
   /// Gets an Ardite Schema Definition from a file. Aims to support mainly the
-  /// JSON and YAML formats.
-  // TODO: validate file against JSON schema.
+  /// JSON and YAML formats. Like `schema.schema.yml`, a type or a schema with
+  /// a keyword its kind does not allow is an error.
   pub fn from_file(path: PathBuf) -> Result<Definition, Error> {
     let extension = path.extension().map_or("", |s| s.to_str().unwrap());
     let file = try!(File::open(&path));
@@ -77,8 +137,14 @@ impl Definition {
 /// Represents a high-level database type.
 #[derive(PartialEq, Debug)]
 pub struct Type {
+  /// The name of the type. Drivers use this to find the collection the type
+  /// maps to. Set when the type is added to a `Definition`.
+  name: Key,
   /// A type may optionally have its own driver.
   driver: Option<DriverConfig>,
+  /// The property which uniquely identifies a value of the type. References
+  /// to the type store the value of this property. Defaults to `id`.
+  key: Key,
   /// The schema used to validate data which claims to be of this type.
   schema: SchemaObject
 }
@@ -87,11 +153,23 @@ impl Type {
   /// Create a new instance of `Type`.
   pub fn new() -> Self {
     Type {
+      name: Key::new(),
       driver: None,
+      key: Key::from("id"),
       schema: SchemaObject::new()
     }
   }
 
+  /// Set the name of the type.
+  pub fn set_name<K>(&mut self, name: K) where K: Into<Key> {
+    self.name = name.into();
+  }
+
+  /// Get the name of the type.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
   /// Set the driver config.
   pub fn set_driver(&mut self, driver: DriverConfig) {
     self.driver = Some(driver);
@@ -102,19 +180,44 @@ impl Type {
     self.driver.as_ref()
   }
 
+  /// Set the key property.
+  pub fn set_key<K>(&mut self, key: K) where K: Into<Key> {
+    self.key = key.into();
+  }
+
+  /// Get the key property.
+  pub fn key(&self) -> &str {
+    &self.key
+  }
+
+  /// Get the schema used to validate values of the type.
+  pub fn schema(&self) -> &SchemaObject {
+    &self.schema
+  }
+
   // Proxy stuffs.
   #[inline] pub fn add_property<K, S>(&mut self, key: K, schema: S) where K: Into<Key>, S: Schema + 'static { self.schema.add_property(key, schema); }
   #[inline] pub fn add_boxed_property<K>(&mut self, key: K, schema: BoxedSchema) where K: Into<Key> { self.schema.add_boxed_property(key, schema); }
   #[inline] pub fn set_required<K>(&mut self, required: Vec<K>) where K: Into<Key> { self.schema.set_required(required) }
+  #[inline] pub fn add_boxed_pattern_property(&mut self, pattern: Regex, schema: BoxedSchema) { self.schema.add_boxed_pattern_property(pattern, schema); }
   #[inline] pub fn enable_additional_properties(&mut self) { self.schema.enable_additional_properties() }
+  #[inline] pub fn set_boxed_additional_properties(&mut self, schema: BoxedSchema) { self.schema.set_boxed_additional_properties(schema) }
   #[inline] pub fn properties(&self) -> LinearMap<Key, &Schema> { self.schema.properties() }
   #[inline] pub fn required(&self) -> &Vec<Key> { self.schema.required() }
-  #[inline] pub fn additional_properties(&self) -> bool { self.schema.additional_properties() }
+  #[inline] pub fn pattern_properties(&self) -> Vec<(&Regex, &Schema)> { self.schema.pattern_properties() }
+  #[inline] pub fn additional_properties(&self) -> Option<&Schema> { self.schema.additional_properties() }
+  #[inline] pub fn get_property(&self, key: &str) -> Option<&Schema> { self.schema.get_property(key) }
+  #[inline] pub fn fill_defaults(&self, value: &mut Value) { self.schema.fill_defaults(value) }
+  #[inline] pub fn validate_value(&self, value: &Value) -> Result<(), Error> { self.schema.validate_value(value) }
+  #[inline] pub fn validate_query(&self, query: &Query) -> Result<(), Error> { self.schema.validate_query(query) }
+  #[inline] pub fn validate_pinned_query(&self, query: &Query, condition: &Condition) -> Result<(), Error> { self.schema.validate_pinned_query(query, condition) }
+  #[inline] pub fn validate_condition(&self, condition: &Condition) -> Result<(), Error> { self.schema.validate_condition(condition) }
+  #[inline] pub fn validate_sort(&self, sort: &[SortRule]) -> Result<(), Error> { self.schema.validate_sort(sort) }
 }
 
 /// Configuration for what driver to use and what URL to use to connect that
-/// driver.
-// TODO: can't finalize this until dynamic loading of drivers is implemented.
+/// driver. The driver is found by the scheme of the URL in a
+/// `DriverRegistry`.
 #[derive(PartialEq, Debug)]
 pub struct DriverConfig {
   /// The URL to pass into the driver when connecting.
@@ -2,9 +2,13 @@
 //! Definition, a format which is used to imperitevly define the data interface
 //! used with Ardite services.
 
+mod default;
 mod definition;
 mod schema;
+mod report;
 mod de;
 
 pub use schema::schema::*;
+pub use schema::default::{SchemaDefault, Generator};
+pub use schema::report::{Report, Failure};
 pub use schema::definition::{Definition, Type, DriverConfig};
@@ -0,0 +1,194 @@
+//! Collects all of the failures found when validating a query or a value
+//! against a schema.
+
+use error::{Error, BadRequest};
+use value::{Object, Pointer, Value};
+
+/// A report of every failure found while validating against a schema. Instead
+/// of stopping at the first failure, schemas add all of their failures to the
+/// report so that a user may fix everything in one go.
+#[derive(PartialEq, Debug)]
+pub struct Report {
+  /// All of the failures found so far.
+  failures: Vec<Failure>
+}
+
+impl Report {
+  /// Creates a new empty report.
+  pub fn new() -> Self {
+    Report {
+      failures: Vec::new()
+    }
+  }
+
+  /// Adds a failure at a certain pointer to the report. The keyword is the
+  /// name of the schema property which failed, like `minimum` or `required`.
+  pub fn add<S>(&mut self, pointer: Pointer, keyword: S, error: Error) where S: Into<String> {
+    self.failures.push(Failure {
+      pointer: pointer,
+      keyword: keyword.into(),
+      error: error
+    });
+  }
+
+  /// Moves every failure of another report into this report.
+  pub fn append(&mut self, other: Report) {
+    self.failures.extend(other.failures);
+  }
+
+  /// Returns true if no failures have been reported.
+  pub fn is_empty(&self) -> bool {
+    self.failures.is_empty()
+  }
+
+  /// Get all of the reported failures.
+  pub fn failures(&self) -> &Vec<Failure> {
+    &self.failures
+  }
+
+  /// Turns the report into a result. If there were no failures the result is
+  /// ok, otherwise an error containing every failure is returned.
+  ///
+  /// When there is only one failure, the error takes the message and hint of
+  /// that failure.
+  pub fn into_result(self) -> Result<(), Error> {
+    match self.failures.len() {
+      0 => Ok(()),
+      1 => {
+        let (message, hint) = {
+          let error = &self.failures[0].error;
+          (error.message().to_owned(), error.hint().map(str::to_owned))
+        };
+        let error = Error::new(BadRequest, message);
+        let error = if let Some(hint) = hint { error.set_hint(hint) } else { error };
+        Err(error.set_failures(self.failures))
+      },
+      length => {
+        let message = format!(
+          "Validation failed in {} places. {}",
+          length,
+          self.failures.iter().map(|failure| failure.error.message()).collect::<Vec<_>>().join(" ")
+        );
+        Err(
+          Error::new(BadRequest, message)
+          .set_hint("Try fixing every failure listed in `errors`.")
+          .set_failures(self.failures)
+        )
+      }
+    }
+  }
+}
+
+/// A single failure in a validation report.
+#[derive(PartialEq, Debug)]
+pub struct Failure {
+  /// Where the failure happened in the validated query or value.
+  pointer: Pointer,
+  /// The schema property which failed.
+  keyword: String,
+  /// The error describing the failure.
+  error: Error
+}
+
+impl Failure {
+  /// Get the pointer to where the failure happened.
+  pub fn pointer(&self) -> &Pointer {
+    &self.pointer
+  }
+
+  /// Formats the pointer as a [JSON pointer][1] string, escaping `~` and `/`
+  /// in keys. The root pointer is the empty string.
+  ///
+  /// [1]: https://tools.ietf.org/html/rfc6901
+  pub fn pointer_string(&self) -> String {
+    self.pointer.iter().map(|key| format!("/{}", key.replace("~", "~0").replace("/", "~1"))).collect()
+  }
+
+  /// Get the schema property which failed.
+  pub fn keyword(&self) -> &str {
+    &self.keyword
+  }
+
+  /// Get the error describing the failure.
+  pub fn error(&self) -> &Error {
+    &self.error
+  }
+
+  /// Gets an object which represents the failure. The pointer is formatted
+  /// with `pointer_string`.
+  pub fn to_value(&self) -> Value {
+    let mut object = Object::new();
+
+    object.insert("pointer".to_owned(), Value::String(self.pointer_string()));
+    object.insert("keyword".to_owned(), Value::String(self.keyword.clone()));
+    object.insert("message".to_owned(), Value::String(self.error.message().to_owned()));
+
+    if let Some(hint) = self.error.hint() {
+      object.insert("hint".to_owned(), Value::String(hint.to_owned()));
+    }
+
+    Value::Object(object)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use error::{Error, BadRequest};
+  use schema::Report;
+
+  #[test]
+  fn test_into_result() {
+    assert!(Report::new().into_result().is_ok());
+    let mut report = Report::new();
+    report.add(point!["a"], "minimum", Error::invalid("Too small.", "Make it bigger."));
+    let error = report.into_result().unwrap_err();
+    assert_eq!(error.code(), &BadRequest);
+    assert_eq!(error.message(), "Too small.");
+    assert_eq!(error.hint(), Some("Make it bigger."));
+    assert_eq!(error.failures().len(), 1);
+    let mut report = Report::new();
+    report.add(point!["a"], "minimum", Error::invalid("Too small.", "Make it bigger."));
+    report.add(point!["b", "c"], "pattern", Error::invalid("No match.", "Match it."));
+    let error = report.into_result().unwrap_err();
+    error.expect("Validation failed in 2 places. Too small. No match.");
+    assert_eq!(error.failures().len(), 2);
+    assert_eq!(error.failures()[1].pointer(), &point!["b", "c"]);
+    assert_eq!(error.failures()[1].keyword(), "pattern");
+  }
+
+  #[test]
+  fn test_to_value() {
+    let mut report = Report::new();
+    report.add(point!["a/b", "~c"], "required", Error::invalid("Missing.", "Add it."));
+    report.add(point![], "type", Error::invalid("Wrong type.", "Change it."));
+    assert_eq!(report.into_result().unwrap_err().to_value(), value!({
+      "error" => true,
+      "message" => "Validation failed in 2 places. Missing. Wrong type.",
+      "hint" => "Try fixing every failure listed in `errors`.",
+      "errors" => [
+        {
+          "pointer" => "/a~1b/~0c",
+          "keyword" => "required",
+          "message" => "Missing.",
+          "hint" => "Add it."
+        },
+        {
+          "pointer" => "",
+          "keyword" => "type",
+          "message" => "Wrong type.",
+          "hint" => "Change it."
+        }
+      ]
+    }));
+  }
+
+  #[test]
+  fn test_display() {
+    let mut report = Report::new();
+    report.add(point!["a/b", "~c"], "required", Error::invalid("Missing.", "Add it."));
+    report.add(point![], "type", Error::invalid("Wrong type.", "Change it."));
+    let display = format!("{}", report.into_result().unwrap_err());
+    assert!(display.contains("\nfailure: \"/a~1b/~0c\" (required) Missing."));
+    assert!(display.contains("\nfailure: \"\" (type) Wrong type."));
+  }
+}
@@ -1,16 +1,25 @@
 //! Format for defining the shape of data in an Ardite Schema Definition.
 
-use std::fmt::Debug;
+use std::any::Any;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::ops::Deref;
+use chrono::{DateTime, NaiveDate};
 use linear_map::LinearMap;
 use regex::Regex;
+use url::Url;
 
 use error::Error;
-use query::Query;
+use query::{Condition, SortRule, Query};
+use schema::{Definition, Report, SchemaDefault};
 use value::{Key, Pointer, Value};
 
 lazy_static! {
   static ref INTEGER_RE: Regex = Regex::new(r"^\d+$").unwrap();
+  static ref EMAIL_RE: Regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
+  static ref DATE_RE: Regex = Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap();
+  static ref UUID_RE: Regex = Regex::new(r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$").unwrap();
 }
 
 pub type BoxedSchema = Box<Schema + 'static>;
@@ -25,6 +34,7 @@ pub type BoxedSchema = Box<Schema + 'static>;
 ///    Nested schemas must be retrievable and this goal is not possible with
 ///    JSON Schema constructs like `oneOf`, `allOf`, `noneOf`, or `not` make it
 ///    difficult (if not impossible) to find a single schema for a pointer.
+///    The exception is `oneOf` with a `discriminator`, see `SchemaUnion`.
 ///
 /// 2. Schema extension. In some areas, adding new properties to the schema
 ///    which don’t have strict validation purposes is useful. For example
@@ -41,10 +51,98 @@ pub trait Schema: Debug {
   /// Used to get a nested schema at a certain point.
   fn get(&self, mut pointer: Pointer) -> Option<&Schema>;
 
+  /// Gets the schema as `Any` so that a trait object may be downcast to its
+  /// concrete schema type. See `downcast_ref`.
+  fn as_any(&self) -> &Any;
+
+  /// Clones the schema into a new box.
+  fn clone_boxed(&self) -> BoxedSchema;
+
+  /// Gets the default used in place of a missing value. See
+  /// `SchemaObject::fill_defaults`.
+  fn default(&self) -> Option<&SchemaDefault>;
+
+  /// Sets the default used in place of a missing value.
+  fn set_default(&mut self, default: SchemaDefault);
+
+  /// Gets the names of every type referenced with `$type` by this schema or
+  /// any schema nested in it.
+  fn references(&self) -> Vec<&str> {
+    Vec::new()
+  }
+
+  /// Resolves every `SchemaReference` nested in this schema with the types of
+  /// a definition. See `Definition::resolve_references`.
+  fn resolve_references(&mut self, _: &Definition) {}
+
+  /// Adds every failure of a query to the report. The pointer is where in the
+  /// full query this schema is being compared.
+  fn report_query(&self, report: &mut Report, pointer: Pointer, query: &Query);
+
+  /// Adds every failure of a value to the report. The pointer is where in the
+  /// full value this schema is being compared.
+  fn report_value(&self, report: &mut Report, pointer: Pointer, value: &Value);
+
   /// Validates a query that a user would like to make on the database by
   /// comparing it to the schema. Mostly checks that all properties described
   /// in the query are accessible according to the schema.
-  fn validate_query(&self, query: &Query) -> Result<(), Error>;
+  fn validate_query(&self, query: &Query) -> Result<(), Error> {
+    let mut report = Report::new();
+    self.report_query(&mut report, Pointer::new(), query);
+    report.into_result()
+  }
+
+  /// Validates a query like `validate_query` for values read with a
+  /// condition. Unions which the condition pins to a variant are validated
+  /// against only that variant.
+  fn validate_pinned_query(&self, query: &Query, condition: &Condition) -> Result<(), Error> {
+    let mut report = Report::new();
+    if let Some(schema) = self.get(Pointer::new()) {
+      report_pinned_query(schema, &mut report, Pointer::new(), query, condition);
+    }
+    report.into_result()
+  }
+
+  /// Validates a value against the schema. This should be run on all data
+  /// before it is handed to a driver.
+  fn validate_value(&self, value: &Value) -> Result<(), Error> {
+    let mut report = Report::new();
+    self.report_value(&mut report, Pointer::new(), value);
+    report.into_result()
+  }
+
+  /// Validates a condition that a user would like to filter by. Checks that
+  /// every property in the condition is accessible according to the schema,
+  /// and that every operator is compatible with the type of its property.
+  fn validate_condition(&self, condition: &Condition) -> Result<(), Error> {
+    let mut report = Report::new();
+    // Getting the empty pointer gives us this schema as a trait object.
+    report_condition(self.get(Pointer::new()), &mut report, Pointer::new(), condition);
+    report.into_result()
+  }
+
+  /// Validates the rules a user would like to sort by. Checks that every
+  /// sorted property is accessible according to the schema and is not an
+  /// object or an array.
+  fn validate_sort(&self, sort: &[SortRule]) -> Result<(), Error> {
+    let mut report = Report::new();
+    if let Some(schema) = self.get(Pointer::new()) {
+      for rule in sort {
+        match lookup(schema, rule.property()) {
+          Lookup::Known(property_schema) => match operand_schema(property_schema).and_then(schema_type) {
+            Some(type_) if type_ == "object" || type_ == "array" => report.add(rule.property().clone(), "type", Error::invalid(
+              format!("Cannot sort by a property of type {}.", type_),
+              "Try sorting by a primitive property like a number or a string."
+            )),
+            _ => {}
+          },
+          Lookup::Unknown => {},
+          Lookup::Invalid(keyword, error) => report.add(rule.property().clone(), keyword, error)
+        }
+      }
+    }
+    report.into_result()
+  }
 }
 
 impl Schema {
@@ -68,6 +166,13 @@ impl Schema {
     SchemaNumber::new()
   }
 
+  /// Create a schema which validates an integer.
+  pub fn integer() -> SchemaNumber {
+    let mut schema = SchemaNumber::new();
+    schema.enable_integer();
+    schema
+  }
+
   /// Create a schema which validates a string.
   pub fn string() -> SchemaString {
     SchemaString::new()
@@ -88,6 +193,32 @@ impl Schema {
   pub fn enum_<V>(values: Vec<V>) -> SchemaEnum where V: Into<Value> {
     SchemaEnum::new(values.into_iter().map(Into::into).collect())
   }
+
+  /// Creates a schema which validates a value of another type in the
+  /// definition.
+  pub fn reference<K>(name: K) -> SchemaReference where K: Into<Key> {
+    SchemaReference::new(name)
+  }
+
+  /// Creates a schema which validates an object with one of many object
+  /// schemas, picked by the value of the discriminator property.
+  pub fn union<K>(discriminator: K) -> SchemaUnion where K: Into<Key> {
+    SchemaUnion::new(discriminator)
+  }
+}
+
+impl<'a> Schema + 'a {
+  /// Downcasts a schema trait object to a reference of its concrete type.
+  /// Returns `None` if the schema is not of that type.
+  pub fn downcast_ref<S>(&self) -> Option<&S> where S: Schema + 'static {
+    self.as_any().downcast_ref::<S>()
+  }
+}
+
+impl Clone for BoxedSchema {
+  fn clone(&self) -> Self {
+    self.clone_boxed()
+  }
 }
 
 // We need equality checks in our tests, however, comparing equality on a trait
@@ -99,9 +230,20 @@ impl<'a, 'b> PartialEq<Schema + 'b> for Schema + 'a {
   }
 }
 
-pub trait SchemaPrimitive: Debug {}
+/// A schema which can not be searched into. Only the value validation needs to
+/// be implemented, `get` and `report_query` are shared by all primitives.
+pub trait SchemaPrimitive: Debug {
+  /// Gets the default of the primitive. See `Schema::default`.
+  fn primitive_default(&self) -> Option<&SchemaDefault>;
+
+  /// Sets the default of the primitive. See `Schema::set_default`.
+  fn set_primitive_default(&mut self, default: SchemaDefault);
 
-impl<'a, T> Schema for T where T: SchemaPrimitive + 'a {
+  /// Adds every failure of a primitive value to the report.
+  fn report_primitive(&self, report: &mut Report, pointer: Pointer, value: &Value);
+}
+
+impl<T> Schema for T where T: SchemaPrimitive + Clone + 'static {
   fn get(&self, pointer: Pointer) -> Option<&Schema> {
     if pointer.is_empty() {
       Some(self)
@@ -110,13 +252,369 @@ impl<'a, T> Schema for T where T: SchemaPrimitive + 'a {
     }
   }
 
-  fn validate_query(&self, query: &Query) -> Result<(), Error> {
-    match *query {
-      Query::All => Ok(()),
-      Query::Keys(_) => Err(Error::invalid(
+  fn as_any(&self) -> &Any {
+    self
+  }
+
+  fn clone_boxed(&self) -> BoxedSchema {
+    Box::new(self.clone())
+  }
+
+  fn default(&self) -> Option<&SchemaDefault> {
+    self.primitive_default()
+  }
+
+  fn set_default(&mut self, default: SchemaDefault) {
+    self.set_primitive_default(default);
+  }
+
+  fn report_query(&self, report: &mut Report, pointer: Pointer, query: &Query) {
+    if let Query::Keys(_) = *query {
+      report.add(pointer, "type", Error::invalid(
         "Cannot deeply query a primitive value.",
         "Try not querying specific properties of a primitive like `null` or `boolean`."
+      ));
+    }
+  }
+
+  fn report_value(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    self.report_primitive(report, pointer, value);
+  }
+}
+
+/// Formats a value for use in an error message.
+fn display_value(value: &Value) -> String {
+  value.to_json().unwrap_or_else(|_| format!("{:?}", value))
+}
+
+/// Creates the error for when a value is not the type a schema expects.
+fn invalid_type(expected: &str, value: &Value) -> Error {
+  Error::invalid(
+    format!("Value {} is not of type {}.", display_value(value), expected),
+    format!("Try using a value of type {} instead.", expected)
+  )
+}
+
+/// Checks if a number is a multiple of another number. Floating point
+/// remainders are not exact so the remainder may be off from zero (or from
+/// the multiple itself) by a tolerance relative to the multiple.
+fn is_multiple_of(number: f64, multiple_of: f64) -> bool {
+  let multiple_of = multiple_of.abs();
+  let remainder = (number % multiple_of).abs();
+  let tolerance = 1e-9 * multiple_of;
+  remainder <= tolerance || multiple_of - remainder <= tolerance
+}
+
+/// 2^63 as a float. Every float in `[-I64_BOUND, I64_BOUND)` without a
+/// fractional part is exactly representable as an `i64`.
+const I64_BOUND: f64 = 9223372036854775808.0;
+
+/// Exactly compares an integer to a float bound without casting the integer to
+/// a float, which would lose precision above 2^53.
+fn compare_integer(integer: i64, bound: f64) -> Ordering {
+  if bound >= I64_BOUND {
+    Ordering::Less
+  } else if bound < -I64_BOUND {
+    Ordering::Greater
+  } else {
+    let floor = bound.floor();
+    match integer.cmp(&(floor as i64)) {
+      Ordering::Equal if bound > floor => Ordering::Less,
+      ordering => ordering
+    }
+  }
+}
+
+/// The schema a condition or sort rule refers to with a pointer.
+enum Lookup<'a> {
+  /// The schema describes the pointer.
+  Known(&'a Schema),
+  /// The schema allows the pointer, but does not describe it. For example a
+  /// property allowed by `additionalProperties`.
+  Unknown,
+  /// The schema does not allow the pointer.
+  Invalid(&'static str, Error)
+}
+
+/// Looks up the schema for a pointer one key at a time so that we know why a
+/// pointer could not be found.
+fn lookup<'a>(schema: &'a Schema, pointer: &[Key]) -> Lookup<'a> {
+  let mut schema = schema;
+  for (i, key) in pointer.iter().enumerate() {
+    // Variants may define the same property differently, so the rest of the
+    // pointer is looked up in every variant of a union.
+    if let Some(union) = schema.downcast_ref::<SchemaUnion>() {
+      if *key != union.discriminator {
+        return lookup_variants(union, &pointer[i..]);
+      }
+    }
+    // A reference is stored as a key, so it has no properties to look up.
+    if let Some(reference) = get_reference(schema, key) {
+      return match pointer.get(i + 1) {
+        Some(next_key) => Lookup::Invalid("$type", reference_property_error(reference, next_key)),
+        None => Lookup::Known(reference)
+      };
+    }
+    if let Some(sub_schema) = schema.get(vec![key.to_owned()]) {
+      schema = sub_schema;
+      continue;
+    }
+    return if schema.downcast_ref::<SchemaNone>().is_some() {
+      Lookup::Unknown
+    } else if schema.downcast_ref::<SchemaObject>().is_some() {
+      Lookup::Invalid("properties", Error::invalid(
+        format!("Object property \"{}\" is not defined in the schema.", key),
+        "Try using an object property that is defined in the schema."
+      ))
+    } else if schema.downcast_ref::<SchemaArray>().is_some() {
+      if INTEGER_RE.is_match(key) {
+        Lookup::Unknown
+      } else {
+        Lookup::Invalid("items", Error::invalid(
+          format!("Cannot use non-integer \"{}\" array property.", key),
+          "Only use integer array keys like 1, 2, and 3."
+        ))
+      }
+    } else {
+      Lookup::Invalid("type", Error::invalid(
+        format!("Cannot use property \"{}\" of a primitive value.", key),
+        "Try not using properties of a primitive like `null` or `boolean`."
       ))
+    };
+  }
+  Lookup::Known(schema)
+}
+
+/// Looks up a pointer in every variant of a union. The pointer is known if
+/// any variant knows it. Otherwise the failure of the first variant defining
+/// the first key of the pointer is used.
+fn lookup_variants<'a>(union: &'a SchemaUnion, pointer: &[Key]) -> Lookup<'a> {
+  let mut unknown = false;
+  let mut invalid = None;
+  for &(_, ref variant) in &union.variants {
+    match lookup(variant, pointer) {
+      Lookup::Known(schema) => return Lookup::Known(schema),
+      Lookup::Unknown => unknown = true,
+      Lookup::Invalid(keyword, error) => {
+        if invalid.is_none() && variant.get_property(&pointer[0]).is_some() {
+          invalid = Some((keyword, error));
+        }
+      }
+    }
+  }
+  match (unknown, invalid) {
+    (true, _) => Lookup::Unknown,
+    (false, Some((keyword, error))) => Lookup::Invalid(keyword, error),
+    (false, None) => Lookup::Invalid("properties", Error::invalid(
+      format!("Object property \"{}\" is not defined in any variant of the schema.", pointer[0]),
+      "Try using an object property that is defined in one of the variants."
+    ))
+  }
+}
+
+/// Gets the reference nested directly in a schema at a key. `Schema::get`
+/// looks through references, so it never finds one.
+fn get_reference<'a>(schema: &'a Schema, key: &str) -> Option<&'a SchemaReference> {
+  let child = if let Some(object) = schema.downcast_ref::<SchemaObject>() {
+    object.get_property(key)
+  } else if let Some(array) = schema.downcast_ref::<SchemaArray>() {
+    if INTEGER_RE.is_match(key) { array.items() } else { None }
+  } else {
+    None
+  };
+  child.and_then(|child| child.downcast_ref::<SchemaReference>())
+}
+
+/// Gets the schema a property is compared and sorted with. A reference is
+/// stored as the key of the referenced value, so it uses the schema of the
+/// key property of the referenced type, or `None` if that is not defined.
+fn operand_schema(schema: &Schema) -> Option<&Schema> {
+  match schema.downcast_ref::<SchemaReference>() {
+    Some(reference) => reference.key_schema(),
+    None => Some(schema)
+  }
+}
+
+/// Creates the error for using a property of a reference in a condition or
+/// a sort rule.
+fn reference_property_error(reference: &SchemaReference, key: &str) -> Error {
+  Error::invalid(
+    format!("Cannot use property \"{}\" of a reference to type '{}'.", key, reference.name()),
+    "Try comparing the reference with the key of the referenced value instead."
+  )
+}
+
+/// Gets the name of the single type a schema accepts. Schemas which accept
+/// more than one type, like `SchemaNone` or `SchemaEnum`, have no type.
+fn schema_type(schema: &Schema) -> Option<&'static str> {
+  if schema.downcast_ref::<SchemaNull>().is_some() { Some("null") }
+  else if schema.downcast_ref::<SchemaBoolean>().is_some() { Some("boolean") }
+  else if schema.downcast_ref::<SchemaNumber>().is_some() { Some("number") }
+  else if schema.downcast_ref::<SchemaString>().is_some() { Some("string") }
+  else if schema.downcast_ref::<SchemaArray>().is_some() { Some("array") }
+  else if schema.downcast_ref::<SchemaObject>().is_some() { Some("object") }
+  else if schema.downcast_ref::<SchemaUnion>().is_some() { Some("object") }
+  else { None }
+}
+
+/// Gets the name of the type of a value.
+fn value_type(value: &Value) -> &'static str {
+  match *value {
+    Value::Null => "null",
+    Value::Boolean(_) => "boolean",
+    Value::I64(_) | Value::F64(_) => "number",
+    Value::String(_) => "string",
+    Value::Array(_) => "array",
+    Value::Object(_) => "object"
+  }
+}
+
+/// Reports a value compared with a property if it can never be equal to the
+/// property. Because missing properties are compared as `null`, a `null`
+/// value is always allowed.
+fn report_operand(schema: &Schema, report: &mut Report, pointer: Pointer, value: &Value) {
+  if let Some(type_) = schema_type(schema) {
+    if *value != Value::Null && value_type(value) != type_ {
+      report.add(pointer, "type", Error::invalid(
+        format!("Cannot compare a property of type {} with {}.", type_, display_value(value)),
+        format!("Try comparing with a value of type {} instead.", type_)
+      ));
+    }
+  }
+}
+
+/// Adds every failure of a query to the report like `Schema::report_query`,
+/// except that a union pinned to a variant by the condition is checked
+/// against only that variant.
+fn report_pinned_query(schema: &Schema, report: &mut Report, pointer: Pointer, query: &Query, condition: &Condition) {
+  let schema = match schema.downcast_ref::<SchemaUnion>().and_then(|union| union.pin(condition)) {
+    Some(variant) => variant as &Schema,
+    None => schema
+  };
+  match (schema.downcast_ref::<SchemaObject>(), query) {
+    (Some(object), &Query::Keys(ref keys)) => {
+      for (key, sub_query) in keys.iter() {
+        match (object.get_property(key), property_condition(condition, key)) {
+          (Some(property_schema), Some(property_condition)) => {
+            let mut sub_pointer = pointer.clone();
+            sub_pointer.push(key.to_owned());
+            report_pinned_query(property_schema, report, sub_pointer, sub_query, property_condition);
+          },
+          _ => object.report_query(report, pointer.clone(), &Query::Keys(linear_map! { key.to_owned() => sub_query.clone() }))
+        }
+      }
+    },
+    _ => schema.report_query(report, pointer, query)
+  }
+}
+
+/// Gets the condition on a property, either directly or in an `And`
+/// condition.
+fn property_condition<'a>(condition: &'a Condition, key: &str) -> Option<&'a Condition> {
+  match *condition {
+    Condition::Keys(ref keys) => keys.get(key),
+    Condition::And(ref conditions) => conditions.iter().filter_map(|condition| property_condition(condition, key)).next(),
+    _ => None
+  }
+}
+
+/// Adds every failure of a condition to the report. The schema is `None` when
+/// the schema allows, but does not describe, the compared value.
+fn report_condition(schema: Option<&Schema>, report: &mut Report, pointer: Pointer, condition: &Condition) {
+  let reference = schema.and_then(|schema| schema.downcast_ref::<SchemaReference>());
+  let operand = schema.and_then(operand_schema);
+  let type_ = operand.and_then(schema_type);
+  match *condition {
+    Condition::True | Condition::False | Condition::Exists => {},
+    Condition::Not(ref condition) => report_condition(schema, report, pointer, condition),
+    Condition::And(ref conditions) | Condition::Or(ref conditions) => {
+      for condition in conditions {
+        report_condition(schema, report, pointer.clone(), condition);
+      }
+    },
+    Condition::Keys(ref keys) => {
+      if let Some(reference) = reference {
+        for key in keys.keys() {
+          let mut sub_pointer = pointer.clone();
+          sub_pointer.push(key.to_owned());
+          report.add(sub_pointer, "$type", reference_property_error(reference, key));
+        }
+      } else if let Some(schema) = schema {
+        // A union pinned to a variant by the condition is checked against
+        // only that variant.
+        let schema = match schema.downcast_ref::<SchemaUnion>().and_then(|union| union.pin(condition)) {
+          Some(variant) => variant as &Schema,
+          None => schema
+        };
+        for (key, condition) in keys.iter() {
+          // Without a pin, a property defined by some variants of a union is
+          // checked against every variant which defines it.
+          if let Some(union) = schema.downcast_ref::<SchemaUnion>() {
+            if union.variants.iter().any(|&(_, ref variant)| variant.get_property(key).is_some()) {
+              let property_condition = Condition::Keys(linear_map! { key.to_owned() => condition.clone() });
+              union.report_any_variant(report, key, |variant, variant_report| {
+                report_condition(Some(variant as &Schema), variant_report, pointer.clone(), &property_condition)
+              });
+              continue;
+            }
+          }
+          let mut sub_pointer = pointer.clone();
+          sub_pointer.push(key.to_owned());
+          match lookup(schema, &[key.to_owned()]) {
+            Lookup::Known(sub_schema) => report_condition(Some(sub_schema), report, sub_pointer, condition),
+            Lookup::Unknown => report_condition(None, report, sub_pointer, condition),
+            Lookup::Invalid(keyword, error) => report.add(sub_pointer, keyword, error)
+          }
+        }
+      }
+    },
+    Condition::Equal(ref value) => {
+      if let Some(operand) = operand {
+        report_operand(operand, report, pointer, value);
+      }
+    },
+    Condition::GreaterThan(ref value) |
+    Condition::GreaterThanOrEqual(ref value) |
+    Condition::LessThan(ref value) |
+    Condition::LessThanOrEqual(ref value) => {
+      match type_ {
+        Some("number") | Some("string") => report_operand(operand.unwrap(), report, pointer, value),
+        Some(type_) => report.add(pointer, "type", Error::invalid(
+          format!("Cannot order a property of type {}.", type_),
+          "Try only using `$gt`, `$gte`, `$lt`, and `$lte` on number or string properties."
+        )),
+        None => {}
+      }
+    },
+    Condition::In(ref values) | Condition::NotIn(ref values) => {
+      if let Some(operand) = operand {
+        for value in values {
+          report_operand(operand, report, pointer.clone(), value);
+        }
+      }
+    },
+    Condition::Matches(_) => {
+      match type_ {
+        Some("string") | None => {},
+        Some(type_) => report.add(pointer, "type", Error::invalid(
+          format!("Cannot match a pattern against a property of type {}.", type_),
+          "Try only using `$regex` on string properties."
+        ))
+      }
+    },
+    Condition::Contains(ref value) => {
+      match type_ {
+        Some("array") => {
+          if let Some(items) = operand.and_then(|operand| operand.downcast_ref::<SchemaArray>()).and_then(SchemaArray::items).and_then(operand_schema) {
+            report_operand(items, report, pointer, value);
+          }
+        },
+        Some(type_) => report.add(pointer, "type", Error::invalid(
+          format!("Cannot check if a property of type {} contains a value.", type_),
+          "Try only using `$contains` on array properties."
+        )),
+        None => {}
+      }
     }
   }
 }
@@ -124,12 +622,17 @@ impl<'a, T> Schema for T where T: SchemaPrimitive + 'a {
 /// There is no schema. No validations should occur. Does not represent the
 /// abscense of any value, only represents that a schema does not define the
 /// data structure at this point.
-#[derive(PartialEq, Debug)]
-pub struct SchemaNone;
+#[derive(Clone, PartialEq, Debug)]
+pub struct SchemaNone {
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
+}
 
 impl SchemaNone {
   pub fn new() -> Self {
-    SchemaNone
+    SchemaNone {
+      default: None
+    }
   }
 }
 
@@ -142,41 +645,99 @@ impl Schema for SchemaNone {
     }
   }
 
-  fn validate_query(&self, _: &Query) -> Result<(), Error> {
-    Ok(())
+  fn as_any(&self) -> &Any {
+    self
+  }
+
+  fn clone_boxed(&self) -> BoxedSchema {
+    Box::new(self.clone())
+  }
+
+  fn default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
   }
+
+  fn report_query(&self, _: &mut Report, _: Pointer, _: &Query) {}
+
+  fn report_value(&self, _: &mut Report, _: Pointer, _: &Value) {}
 }
 
 /// Represents the absence of any value.
-#[derive(PartialEq, Debug)]
-pub struct SchemaNull;
+#[derive(Clone, PartialEq, Debug)]
+pub struct SchemaNull {
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
+}
 
 impl SchemaNull {
   pub fn new() -> Self {
-    SchemaNull
+    SchemaNull {
+      default: None
+    }
   }
 }
 
-impl SchemaPrimitive for SchemaNull {}
+impl SchemaPrimitive for SchemaNull {
+  fn primitive_default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_primitive_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
+  }
+
+  fn report_primitive(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    match *value {
+      Value::Null => {},
+      _ => report.add(pointer, "type", invalid_type("null", value))
+    }
+  }
+}
 
 /// Represents a binary true/false value.
-#[derive(PartialEq, Debug)]
-pub struct SchemaBoolean;
+#[derive(Clone, PartialEq, Debug)]
+pub struct SchemaBoolean {
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
+}
 
 impl SchemaBoolean {
   pub fn new() -> Self {
-    SchemaBoolean
+    SchemaBoolean {
+      default: None
+    }
   }
 }
 
-impl SchemaPrimitive for SchemaBoolean {}
+impl SchemaPrimitive for SchemaBoolean {
+  fn primitive_default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_primitive_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
+  }
+
+  fn report_primitive(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    match *value {
+      Value::Boolean(_) => {},
+      _ => report.add(pointer, "type", invalid_type("boolean", value))
+    }
+  }
+}
 
 /// Represents a numeric type.
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct SchemaNumber {
-  /// Forces the number to be a multiple of another. This helps in specifying
-  /// integers if this value is `Some(1)` for example.
-  multiple_of: Option<f32>,
+  /// Whether or not the number must be an integer. Integers are `I64`
+  /// values, or `F64` values without a fractional part. Default is `false`.
+  integer: bool,
+  /// Forces the number to be a multiple of another.
+  multiple_of: Option<f64>,
   /// The minimum value the number can be.
   minimum: Option<f64>,
   /// Whether or not the minimum value should be included when validating.
@@ -186,43 +747,122 @@ pub struct SchemaNumber {
   maximum: Option<f64>,
   /// Whether or not the maximum value should be included when validating.
   /// Default is `false`.
-  exclusive_maximum: bool
+  exclusive_maximum: bool,
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
 }
 
 impl SchemaNumber {
   pub fn new() -> Self {
     SchemaNumber {
+      integer: false,
       multiple_of: None,
       minimum: None,
       exclusive_minimum: false,
       maximum: None,
-      exclusive_maximum: false
+      exclusive_maximum: false,
+      default: None
     }
   }
 
-  pub fn set_multiple_of(&mut self, multiple_of: f32) { self.multiple_of = Some(multiple_of); }
+  pub fn enable_integer(&mut self) { self.integer = true; }
+  pub fn set_multiple_of(&mut self, multiple_of: f64) { self.multiple_of = Some(multiple_of); }
   pub fn set_minimum(&mut self, minimum: f64) { self.minimum = Some(minimum); }
   pub fn enable_exclusive_minimum(&mut self) { self.exclusive_minimum = true; }
   pub fn set_maximum(&mut self, maximum: f64) { self.maximum = Some(maximum); }
   pub fn enable_exclusive_maximum(&mut self) { self.exclusive_maximum = true; }
-  pub fn multiple_of(&self) -> Option<f32> { self.multiple_of }
+  pub fn integer(&self) -> bool { self.integer }
+  pub fn multiple_of(&self) -> Option<f64> { self.multiple_of }
   pub fn minimum(&self) -> Option<f64> { self.minimum }
   pub fn exclusive_minimum(&self) -> bool { self.exclusive_minimum }
   pub fn maximum(&self) -> Option<f64> { self.maximum }
   pub fn exclusive_maximum(&self) -> bool { self.exclusive_maximum }
 }
 
-impl SchemaPrimitive for SchemaNumber {}
+impl SchemaPrimitive for SchemaNumber {
+  fn primitive_default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_primitive_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
+  }
+
+  fn report_primitive(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    // Keep `I64` values exact, as casting to `f64` loses precision above 2^53.
+    let (number, exact) = match *value {
+      Value::I64(number) => (number as f64, Some(number)),
+      Value::F64(number) => (number, None),
+      _ => return report.add(pointer, "type", invalid_type(if self.integer { "integer" } else { "number" }, value))
+    };
+
+    if self.integer && number.fract() != 0.0 {
+      return report.add(pointer, "type", invalid_type("integer", value));
+    }
+
+    let shown = exact.map_or_else(|| number.to_string(), |exact| exact.to_string());
+    let compare = |bound: f64| match exact {
+      Some(exact) => Some(compare_integer(exact, bound)),
+      None => number.partial_cmp(&bound)
+    };
+
+    if let Some(multiple_of) = self.multiple_of {
+      let is_multiple = match exact {
+        Some(exact) if multiple_of.fract() == 0.0 && multiple_of.abs() >= 1.0 && multiple_of.abs() < I64_BOUND =>
+          exact.checked_rem(multiple_of as i64).map_or(true, |remainder| remainder == 0),
+        _ => is_multiple_of(number, multiple_of)
+      };
+      if !is_multiple {
+        report.add(pointer.clone(), "multipleOf", Error::invalid(
+          format!("Number {} is not a multiple of {}.", shown, multiple_of),
+          format!("Try using a number which is divisible by {}.", multiple_of)
+        ));
+      }
+    }
+
+    if let Some(minimum) = self.minimum {
+      let too_small = match compare(minimum) {
+        Some(Ordering::Less) => true,
+        Some(Ordering::Equal) => self.exclusive_minimum,
+        _ => false
+      };
+      if too_small {
+        report.add(pointer.clone(), "minimum", Error::invalid(
+          format!("Number {} is less than the minimum of {}.", shown, minimum),
+          format!("Try using a number greater than {}{}.", if self.exclusive_minimum { "" } else { "or equal to " }, minimum)
+        ));
+      }
+    }
+
+    if let Some(maximum) = self.maximum {
+      let too_large = match compare(maximum) {
+        Some(Ordering::Greater) => true,
+        Some(Ordering::Equal) => self.exclusive_maximum,
+        _ => false
+      };
+      if too_large {
+        report.add(pointer, "maximum", Error::invalid(
+          format!("Number {} is greater than the maximum of {}.", shown, maximum),
+          format!("Try using a number less than {}{}.", if self.exclusive_maximum { "" } else { "or equal to " }, maximum)
+        ));
+      }
+    }
+  }
+}
 
 /// Represents a string type.
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct SchemaString {
   /// The mimimum length of characters in the string.
   min_length: Option<u64>,
   /// The maximum length of characters in the string.
   max_length: Option<u64>,
   /// A regular expression pattern to validate the string against.
-  pattern: Option<Regex>
+  pattern: Option<Regex>,
+  /// A built in format the string must be in, like an email or a date.
+  format: Option<StringFormat>,
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
 }
 
 impl SchemaString {
@@ -230,32 +870,165 @@ impl SchemaString {
     SchemaString {
       min_length: None,
       max_length: None,
-      pattern: None
+      pattern: None,
+      format: None,
+      default: None
     }
   }
 
   pub fn set_min_length(&mut self, min_length: u64) { self.min_length = Some(min_length); }
   pub fn set_max_length(&mut self, max_length: u64) { self.max_length = Some(max_length); }
   pub fn set_pattern(&mut self, pattern: Regex) { self.pattern = Some(pattern); }
+  pub fn set_format(&mut self, format: StringFormat) { self.format = Some(format); }
   pub fn min_length(&self) -> Option<u64> { self.min_length }
   pub fn max_length(&self) -> Option<u64> { self.max_length }
   pub fn pattern(&self) -> Option<&Regex> { self.pattern.as_ref() }
+  pub fn format(&self) -> Option<StringFormat> { self.format }
+}
+
+impl SchemaPrimitive for SchemaString {
+  fn primitive_default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_primitive_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
+  }
+
+  fn report_primitive(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    let string = match *value {
+      Value::String(ref string) => string,
+      _ => return report.add(pointer, "type", invalid_type("string", value))
+    };
+
+    let length = string.chars().count() as u64;
+
+    if let Some(min_length) = self.min_length {
+      if length < min_length {
+        report.add(pointer.clone(), "minLength", Error::invalid(
+          format!("String \"{}\" is shorter than the minimum length of {}.", string, min_length),
+          format!("Try using a string with at least {} characters.", min_length)
+        ));
+      }
+    }
+
+    if let Some(max_length) = self.max_length {
+      if length > max_length {
+        report.add(pointer.clone(), "maxLength", Error::invalid(
+          format!("String \"{}\" is longer than the maximum length of {}.", string, max_length),
+          format!("Try using a string with at most {} characters.", max_length)
+        ));
+      }
+    }
+
+    if let Some(ref pattern) = self.pattern {
+      if !pattern.is_match(string) {
+        report.add(pointer.clone(), "pattern", Error::invalid(
+          format!("String \"{}\" does not match the pattern /{}/.", string, pattern),
+          "Try using a string which matches the pattern defined in the schema."
+        ));
+      }
+    }
+
+    if let Some(format) = self.format {
+      if !format.is_valid(string) {
+        report.add(pointer, "format", Error::invalid(
+          format!("String \"{}\" is not a valid {}.", string, format.name()),
+          format!("Try using a string in the {} format.", format.name())
+        ));
+      }
+    }
+  }
+}
+
+/// A built in format for strings, named like the JSON Schema `format`
+/// keyword. Drivers may use the format of a string to pick a native type,
+/// like a `timestamp` column for `DateTime`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StringFormat {
+  /// An email address, like `ada@example.com`.
+  Email,
+  /// An absolute URI, parsed with the `url` crate.
+  Uri,
+  /// A date and time in the [RFC 3339][1] format, like
+  /// `2016-05-01T12:00:00Z`.
+  ///
+  /// [1]: https://tools.ietf.org/html/rfc3339
+  DateTime,
+  /// A full date in the RFC 3339 format, like `2016-05-01`.
+  Date,
+  /// A hyphenated UUID, like `8a7c3b2e-6d4f-4e1a-9b0c-1d2e3f4a5b6c`.
+  Uuid,
+  /// An IPv4 address in dotted decimal notation, like `127.0.0.1`.
+  Ipv4,
+  /// An IPv6 address, like `::1`.
+  Ipv6
 }
 
-impl SchemaPrimitive for SchemaString {}
+impl StringFormat {
+  /// Gets a format by the name used for it with `format`.
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "email" => Some(StringFormat::Email),
+      "uri" => Some(StringFormat::Uri),
+      "date-time" => Some(StringFormat::DateTime),
+      "date" => Some(StringFormat::Date),
+      "uuid" => Some(StringFormat::Uuid),
+      "ipv4" => Some(StringFormat::Ipv4),
+      "ipv6" => Some(StringFormat::Ipv6),
+      _ => None
+    }
+  }
+
+  /// Gets the name used for the format with `format`.
+  pub fn name(&self) -> &'static str {
+    match *self {
+      StringFormat::Email => "email",
+      StringFormat::Uri => "uri",
+      StringFormat::DateTime => "date-time",
+      StringFormat::Date => "date",
+      StringFormat::Uuid => "uuid",
+      StringFormat::Ipv4 => "ipv4",
+      StringFormat::Ipv6 => "ipv6"
+    }
+  }
+
+  /// Checks if a string is in the format.
+  pub fn is_valid(&self, string: &str) -> bool {
+    match *self {
+      StringFormat::Email => EMAIL_RE.is_match(string),
+      StringFormat::Uri => Url::parse(string).is_ok(),
+      StringFormat::DateTime => DateTime::parse_from_rfc3339(string).is_ok(),
+      // Chrono also accepts unpadded fields like `2016-5-1`.
+      StringFormat::Date => DATE_RE.is_match(string) && NaiveDate::parse_from_str(string, "%Y-%m-%d").is_ok(),
+      StringFormat::Uuid => UUID_RE.is_match(string),
+      StringFormat::Ipv4 => string.parse::<Ipv4Addr>().is_ok(),
+      StringFormat::Ipv6 => string.parse::<Ipv6Addr>().is_ok()
+    }
+  }
+}
 
 /// Represents a set of any type.
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct SchemaArray {
   /// A schema which all items in the array must match.
   // We use box because the array must take ownership of its child schema.
-  items: Option<BoxedSchema>
+  items: Option<BoxedSchema>,
+  /// The minimum number of items in the array.
+  min_items: Option<u64>,
+  /// The maximum number of items in the array.
+  max_items: Option<u64>,
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
 }
 
 impl SchemaArray {
   pub fn new() -> Self {
     SchemaArray {
-      items: None
+      items: None,
+      min_items: None,
+      max_items: None,
+      default: None
     }
   }
 
@@ -267,9 +1040,15 @@ impl SchemaArray {
     self.items = Some(schema);
   }
 
+  pub fn set_min_items(&mut self, min_items: u64) { self.min_items = Some(min_items); }
+  pub fn set_max_items(&mut self, max_items: u64) { self.max_items = Some(max_items); }
+
   pub fn items(&self) -> Option<&Schema> {
     self.items.as_ref().map(|schema| schema.deref())
   }
+
+  pub fn min_items(&self) -> Option<u64> { self.min_items }
+  pub fn max_items(&self) -> Option<u64> { self.max_items }
 }
 
 impl Schema for SchemaArray {
@@ -289,41 +1068,101 @@ impl Schema for SchemaArray {
     }
   }
 
-  fn validate_query(&self, query: &Query) -> Result<(), Error> {
-    match *query {
-      Query::All => Ok(()),
-      Query::Keys(ref query_properties) => {
-        let err_key = query_properties.keys().map(|key| {
-          if INTEGER_RE.is_match(key) {
-            if let Some(ref items) = self.items {
-              items.validate_query(&query_properties.get(key).unwrap())
-            } else {
-              Ok(())
-            }
-          } else {
-            Err(Error::invalid(format!("Cannot query non-integer \"{}\" array property.", key), "Only query integer array keys like 1, 2, and 3."))
+  fn as_any(&self) -> &Any {
+    self
+  }
+
+  fn clone_boxed(&self) -> BoxedSchema {
+    Box::new(self.clone())
+  }
+
+  fn default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
+  }
+
+  fn references(&self) -> Vec<&str> {
+    self.items.as_ref().map_or(Vec::new(), |items| items.references())
+  }
+
+  fn resolve_references(&mut self, definition: &Definition) {
+    if let Some(ref mut items) = self.items {
+      items.resolve_references(definition);
+    }
+  }
+
+  fn report_query(&self, report: &mut Report, pointer: Pointer, query: &Query) {
+    if let Query::Keys(ref query_properties) = *query {
+      for (key, sub_query) in query_properties.iter() {
+        let mut sub_pointer = pointer.clone();
+        sub_pointer.push(key.to_owned());
+        if INTEGER_RE.is_match(key) {
+          if let Some(ref items) = self.items {
+            items.report_query(report, sub_pointer, sub_query);
           }
-        }).find(|r| r.is_err());
-        match err_key {
-          None => Ok(()),
-          Some(error) => error
+        } else {
+          report.add(sub_pointer, "items", Error::invalid(format!("Cannot query non-integer \"{}\" array property.", key), "Only query integer array keys like 1, 2, and 3."));
         }
       }
     }
   }
+
+  fn report_value(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    match *value {
+      Value::Array(ref array) => {
+        let length = array.len() as u64;
+
+        if let Some(min_items) = self.min_items {
+          if length < min_items {
+            report.add(pointer.clone(), "minItems", Error::invalid(
+              format!("Array has {} items which is fewer than the minimum of {}.", length, min_items),
+              format!("Try using an array with at least {} items.", min_items)
+            ));
+          }
+        }
+
+        if let Some(max_items) = self.max_items {
+          if length > max_items {
+            report.add(pointer.clone(), "maxItems", Error::invalid(
+              format!("Array has {} items which is more than the maximum of {}.", length, max_items),
+              format!("Try using an array with at most {} items.", max_items)
+            ));
+          }
+        }
+
+        if let Some(ref items) = self.items {
+          for (i, item) in array.iter().enumerate() {
+            let mut sub_pointer = pointer.clone();
+            sub_pointer.push(i.to_string());
+            items.report_value(report, sub_pointer, item);
+          }
+        }
+      },
+      _ => report.add(pointer, "type", invalid_type("array", value))
+    }
+  }
 }
 
 /// Represents a set of key/value pairs.
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct SchemaObject {
   /// Schemas associated to the object properties.
   // We use box because the object must take ownership of its child schema.
   properties: LinearMap<Key, BoxedSchema>,
   /// Properties that are required to be in the object.
   required: Vec<Key>,
-  /// Whether or not there may be extra properties outside of the ones
-  /// defined by the properties map.
-  additional_properties: bool
+  /// Schemas for the properties whose keys match a pattern. Only used for
+  /// properties which are not in the properties map.
+  pattern_properties: Vec<(Regex, BoxedSchema)>,
+  /// The schema for extra properties outside of the ones defined by the
+  /// properties map and the pattern properties. If `None`, extra properties
+  /// are not allowed.
+  additional_properties: Option<BoxedSchema>,
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
 }
 
 impl SchemaObject {
@@ -331,7 +1170,9 @@ impl SchemaObject {
     SchemaObject {
       properties: LinearMap::new(),
       required: Vec::new(),
-      additional_properties: false
+      pattern_properties: Vec::new(),
+      additional_properties: None,
+      default: None
     }
   }
 
@@ -347,8 +1188,27 @@ impl SchemaObject {
     self.required = required.into_iter().map(Into::into).collect();
   }
 
+  pub fn add_pattern_property<S>(&mut self, pattern: Regex, schema: S) where S: Schema + 'static {
+    self.pattern_properties.push((pattern, Box::new(schema)));
+  }
+
+  pub fn add_boxed_pattern_property(&mut self, pattern: Regex, schema: BoxedSchema) {
+    self.pattern_properties.push((pattern, schema));
+  }
+
+  /// Allows extra properties of any value.
   pub fn enable_additional_properties(&mut self) {
-    self.additional_properties = true;
+    self.additional_properties = Some(Box::new(SchemaNone::new()));
+  }
+
+  /// Allows extra properties which are valid against a schema, so the object
+  /// may be used as a map.
+  pub fn set_additional_properties<S>(&mut self, schema: S) where S: Schema + 'static {
+    self.additional_properties = Some(Box::new(schema));
+  }
+
+  pub fn set_boxed_additional_properties(&mut self, schema: BoxedSchema) {
+    self.additional_properties = Some(schema);
   }
 
   pub fn properties(&self) -> LinearMap<Key, &Schema> {
@@ -363,8 +1223,55 @@ impl SchemaObject {
     &self.required
   }
 
-  pub fn additional_properties(&self) -> bool {
-    self.additional_properties
+  pub fn pattern_properties(&self) -> Vec<(&Regex, &Schema)> {
+    self.pattern_properties.iter().map(|&(ref pattern, ref schema)| (pattern, &**schema as &Schema)).collect()
+  }
+
+  pub fn additional_properties(&self) -> Option<&Schema> {
+    self.additional_properties.as_ref().map(Deref::deref)
+  }
+
+  /// Gets every schema an object property must be valid against, like in
+  /// JSON Schema. These are the schema in the properties map and the schema
+  /// of every pattern property which matches the key, or the schema for
+  /// additional properties if there are none.
+  pub fn get_property_schemas(&self, key: &str) -> Vec<&Schema> {
+    let mut schemas: Vec<&Schema> = self.properties.get(key).into_iter()
+      .chain(self.pattern_properties.iter().filter(|&&(ref pattern, _)| pattern.is_match(key)).map(|&(_, ref schema)| schema))
+      .map(|schema| &**schema as &Schema)
+      .collect();
+    if schemas.is_empty() {
+      if let Some(ref schema) = self.additional_properties {
+        schemas.push(schema.deref());
+      }
+    }
+    schemas
+  }
+
+  /// Gets the schema for an object property. A property defined in the
+  /// properties map comes first, then the first pattern property in order
+  /// which matches the key, and then the schema for additional properties.
+  pub fn get_property(&self, key: &str) -> Option<&Schema> {
+    self.get_property_schemas(key).into_iter().next()
+  }
+
+  /// Fills in every property missing from an object which has a default in
+  /// the schema, generating a new value for generated defaults. Objects
+  /// nested in the object are filled in as well. Values which are not
+  /// objects are left alone.
+  pub fn fill_defaults(&self, value: &mut Value) {
+    if let Value::Object(ref mut object) = *value {
+      for (key, schema) in self.properties.iter() {
+        if !object.contains_key(key) {
+          if let Some(default) = schema.default() {
+            object.insert(key.to_owned(), default.value());
+          }
+        }
+        if let (Some(property_value), Some(property_schema)) = (object.get_mut(key), schema.downcast_ref::<SchemaObject>()) {
+          property_schema.fill_defaults(property_value);
+        }
+      }
+    }
   }
 }
 
@@ -373,7 +1280,7 @@ impl Schema for SchemaObject {
     if pointer.is_empty() {
       Some(self)
     } else {
-      if let Some(schema) = self.properties.get(&pointer.remove(0)) {
+      if let Some(schema) = self.get_property(&pointer.remove(0)) {
         schema.get(pointer)
       } else {
         None
@@ -381,24 +1288,85 @@ impl Schema for SchemaObject {
     }
   }
 
-  fn validate_query(&self, query: &Query) -> Result<(), Error> {
-    match *query {
-      Query::All => Ok(()),
-      Query::Keys(ref query_properties) => {
-        let err_key = query_properties.keys().map(|key| {
-          if let Some(property_schema) = self.properties.get(key) {
-            property_schema.validate_query(&query_properties.get(key).unwrap())
-          } else if self.additional_properties {
-            Ok(())
-          } else {
-            Err(Error::invalid(format!("Cannot query object property \"{}\".", key), "Query an object property that is defined in the schema."))
-          }
-        }).find(|r| r.is_err());
-        match err_key {
-          None => Ok(()),
-          Some(error) => error
-        }
-      }
+  fn as_any(&self) -> &Any {
+    self
+  }
+
+  fn clone_boxed(&self) -> BoxedSchema {
+    Box::new(self.clone())
+  }
+
+  fn default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
+  }
+
+  fn references(&self) -> Vec<&str> {
+    self.properties.values()
+      .chain(self.pattern_properties.iter().map(|&(_, ref schema)| schema))
+      .chain(self.additional_properties.iter())
+      .flat_map(|schema| schema.references())
+      .collect()
+  }
+
+  fn resolve_references(&mut self, definition: &Definition) {
+    for (_, schema) in self.properties.iter_mut() {
+      schema.resolve_references(definition);
+    }
+    for &mut (_, ref mut schema) in &mut self.pattern_properties {
+      schema.resolve_references(definition);
+    }
+    if let Some(ref mut schema) = self.additional_properties {
+      schema.resolve_references(definition);
+    }
+  }
+
+  fn report_query(&self, report: &mut Report, pointer: Pointer, query: &Query) {
+    if let Query::Keys(ref query_properties) = *query {
+      for (key, sub_query) in query_properties.iter() {
+        let mut sub_pointer = pointer.clone();
+        sub_pointer.push(key.to_owned());
+        if let Some(property_schema) = self.get_property(key) {
+          property_schema.report_query(report, sub_pointer, sub_query);
+        } else {
+          report.add(sub_pointer, "properties", Error::invalid(format!("Cannot query object property \"{}\".", key), "Query an object property that is defined in the schema."));
+        }
+      }
+    }
+  }
+
+  fn report_value(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    let object = match *value {
+      Value::Object(ref object) => object,
+      _ => return report.add(pointer, "type", invalid_type("object", value))
+    };
+
+    for key in &self.required {
+      if !object.contains_key(key) {
+        report.add(pointer.clone(), "required", Error::invalid(
+          format!("Object is missing required property \"{}\".", key),
+          format!("Try adding a value for the \"{}\" property.", key)
+        ));
+      }
+    }
+
+    for (key, property_value) in object.iter() {
+      let mut sub_pointer = pointer.clone();
+      sub_pointer.push(key.to_owned());
+      let property_schemas = self.get_property_schemas(key);
+      if !property_schemas.is_empty() {
+        for property_schema in property_schemas {
+          property_schema.report_value(report, sub_pointer.clone(), property_value);
+        }
+      } else {
+        report.add(sub_pointer, "additionalProperties", Error::invalid(
+          format!("Object property \"{}\" is not allowed.", key),
+          "Try only using object properties that are defined in the schema."
+        ));
+      }
     }
   }
 }
@@ -406,16 +1374,19 @@ impl Schema for SchemaObject {
 /// Represents a value which *must* be one of the defined values. An enum is
 /// considered a primitive type as if it is a single value is a higher order
 /// type, no variation is allowed.
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct SchemaEnum {
   /// The available values.
-  values: Vec<Value>
+  values: Vec<Value>,
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
 }
 
 impl SchemaEnum {
   pub fn new(values: Vec<Value>) -> Self {
     SchemaEnum {
-      values: values
+      values: values,
+      default: None
     }
   }
 
@@ -424,12 +1395,330 @@ impl SchemaEnum {
   }
 }
 
-impl SchemaPrimitive for SchemaEnum {}
+impl SchemaPrimitive for SchemaEnum {
+  fn primitive_default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_primitive_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
+  }
+
+  fn report_primitive(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    if !self.values.contains(value) {
+      report.add(pointer, "enum", Error::invalid(
+        format!("Value {} is not one of the enumerated values.", display_value(value)),
+        format!("Try using one of the following values: {}.", self.values.iter().map(display_value).collect::<Vec<_>>().join(", "))
+      ));
+    }
+  }
+}
+
+/// Represents a value of another type in the definition, referenced by name
+/// with `$type`. The reference behaves exactly like the schema of the
+/// referenced type once it has been resolved with
+/// `Definition::resolve_references`.
+#[derive(Clone)]
+pub struct SchemaReference {
+  /// The name of the referenced type.
+  name: Key,
+  /// The schema of the referenced type. `None` until the reference is
+  /// resolved.
+  schema: Option<SchemaObject>,
+  /// The key property of the referenced type. `None` until the reference is
+  /// resolved.
+  key: Option<Key>,
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
+}
+
+impl SchemaReference {
+  pub fn new<K>(name: K) -> Self where K: Into<Key> {
+    SchemaReference {
+      name: name.into(),
+      schema: None,
+      key: None,
+      default: None
+    }
+  }
+
+  pub fn set_schema(&mut self, schema: SchemaObject) { self.schema = Some(schema); }
+  pub fn set_key<K>(&mut self, key: K) where K: Into<Key> { self.key = Some(key.into()); }
+  pub fn name(&self) -> &str { &self.name }
+  pub fn schema(&self) -> Option<&SchemaObject> { self.schema.as_ref() }
+  pub fn key(&self) -> Option<&str> { self.key.as_ref().map(String::as_str) }
+
+  /// Gets the schema of the key property of the referenced type, which is
+  /// the schema of the reference as it is stored. `None` if the reference is
+  /// not resolved or the key property is not defined.
+  pub fn key_schema(&self) -> Option<&Schema> {
+    match (self.schema.as_ref(), self.key.as_ref()) {
+      (Some(schema), Some(key)) => schema.properties.get(key).map(Deref::deref),
+      _ => None
+    }
+  }
+
+  /// Adds a failure for when the reference is used before being resolved.
+  fn report_unresolved(&self, report: &mut Report, pointer: Pointer) {
+    report.add(pointer, "$type", Error::internal(format!("Reference to type '{}' has not been resolved.", self.name)));
+  }
+}
+
+impl Schema for SchemaReference {
+  fn get(&self, pointer: Pointer) -> Option<&Schema> {
+    self.schema.as_ref().and_then(|schema| schema.get(pointer))
+  }
+
+  fn as_any(&self) -> &Any {
+    self
+  }
+
+  fn clone_boxed(&self) -> BoxedSchema {
+    Box::new(self.clone())
+  }
+
+  fn default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
+  }
+
+  fn references(&self) -> Vec<&str> {
+    vec![self.name.as_str()]
+  }
+
+  fn resolve_references(&mut self, definition: &Definition) {
+    if let Some(type_) = definition.get_type(&self.name) {
+      self.schema = Some(type_.schema().clone());
+      self.key = Some(type_.key().to_owned());
+    }
+  }
+
+  fn report_query(&self, report: &mut Report, pointer: Pointer, query: &Query) {
+    match self.schema {
+      Some(ref schema) => schema.report_query(report, pointer, query),
+      None => self.report_unresolved(report, pointer)
+    }
+  }
+
+  /// A reference is stored as the key of the referenced value, so objects
+  /// are validated against the referenced schema and any other value against
+  /// the key property. If the key property is not defined, a key must be a
+  /// string or a number.
+  fn report_value(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    match (self.schema.as_ref(), value) {
+      (Some(schema), &Value::Object(_)) => schema.report_value(report, pointer, value),
+      (Some(_), _) => match self.key_schema() {
+        Some(key_schema) => key_schema.report_value(report, pointer, value),
+        None => match *value {
+          Value::I64(_) | Value::F64(_) | Value::String(_) => (),
+          _ => report.add(pointer, "$type", Error::invalid(
+            format!("Value {} is not a key of type '{}'.", display_value(value), self.name),
+            "Try using a string or a number as the key of the referenced value."
+          ))
+        }
+      },
+      (None, _) => self.report_unresolved(report, pointer)
+    }
+  }
+}
+
+// A reference is identified by the name of the type it references, so the
+// resolved schema is left out of comparisons and debug output.
+impl PartialEq for SchemaReference {
+  fn eq(&self, other: &SchemaReference) -> bool {
+    self.name == other.name
+  }
+}
+
+impl Debug for SchemaReference {
+  fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+    write!(fmt, "SchemaReference {{ name: {:?} }}", self.name)
+  }
+}
+
+/// Represents an object which is one of many object schemas, called variants.
+/// The variant of an object is picked by the value of its discriminator
+/// property, so unlike `oneOf` the schema of any value can still be found.
+///
+/// When the discriminator is not known, like when getting a nested schema or
+/// validating a query, the union behaves like an object with the properties
+/// of every variant.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SchemaUnion {
+  /// The property whose value picks the variant.
+  discriminator: Key,
+  /// Every variant along with the value of the discriminator which picks it.
+  variants: Vec<(Value, SchemaObject)>,
+  /// An enum of every discriminant, which is the schema of the discriminator
+  /// property when the variant is not known.
+  discriminants: SchemaEnum,
+  /// The value used when none is provided.
+  default: Option<SchemaDefault>
+}
+
+impl SchemaUnion {
+  pub fn new<K>(discriminator: K) -> Self where K: Into<Key> {
+    SchemaUnion {
+      discriminator: discriminator.into(),
+      variants: Vec::new(),
+      discriminants: SchemaEnum::new(Vec::new()),
+      default: None
+    }
+  }
+
+  /// Adds a variant which is picked when the discriminator is equal to the
+  /// discriminant. The discriminator property of the variant is replaced with
+  /// an enum of only the discriminant, and is required.
+  pub fn add_variant<V>(&mut self, discriminant: V, mut variant: SchemaObject) where V: Into<Value> {
+    let discriminant = discriminant.into();
+    variant.add_property(self.discriminator.clone(), Schema::enum_(vec![discriminant.clone()]));
+    if !variant.required().contains(&self.discriminator) {
+      let mut required = variant.required().clone();
+      required.push(self.discriminator.clone());
+      variant.set_required(required);
+    }
+    self.variants.push((discriminant, variant));
+    self.discriminants = SchemaEnum::new(self.variants.iter().map(|&(ref discriminant, _)| discriminant.clone()).collect());
+  }
+
+  pub fn discriminator(&self) -> &str { &self.discriminator }
+
+  /// Gets every variant along with its discriminant, in order.
+  pub fn variants(&self) -> Vec<(&Value, &SchemaObject)> {
+    self.variants.iter().map(|&(ref discriminant, ref variant)| (discriminant, variant)).collect()
+  }
+
+  /// Gets the variant picked by a discriminant. When the discriminant is
+  /// known, nested schemas and queries should use the variant instead of the
+  /// union.
+  pub fn variant(&self, discriminant: &Value) -> Option<&SchemaObject> {
+    self.variants.iter().find(|&&(ref other, _)| other == discriminant).map(|&(_, ref variant)| variant)
+  }
+
+  /// Gets the variant picked by a condition which pins the discriminator to
+  /// a single value, either directly or in an `And` condition.
+  pub fn pin(&self, condition: &Condition) -> Option<&SchemaObject> {
+    match *condition {
+      Condition::Keys(ref keys) => match keys.get(&self.discriminator) {
+        Some(&Condition::Equal(ref discriminant)) => self.variant(discriminant),
+        _ => None
+      },
+      Condition::And(ref conditions) => conditions.iter().filter_map(|condition| self.pin(condition)).next(),
+      _ => None
+    }
+  }
+
+  /// Reports the failures of a check on one property unless the check passes
+  /// for any variant. The failures of the first variant defining the
+  /// property are the ones reported.
+  fn report_any_variant<F>(&self, report: &mut Report, key: &str, check: F) where F: Fn(&SchemaObject, &mut Report) {
+    let variant_reports: Vec<Report> = self.variants.iter().map(|&(_, ref variant)| {
+      let mut variant_report = Report::new();
+      check(variant, &mut variant_report);
+      variant_report
+    }).collect();
+    if !variant_reports.iter().any(Report::is_empty) {
+      let defining = self.variants.iter().position(|&(_, ref variant)| variant.get_property(key).is_some()).unwrap_or(0);
+      if let Some(variant_report) = variant_reports.into_iter().nth(defining) {
+        report.append(variant_report);
+      }
+    }
+  }
+
+  /// Formats every discriminant for use in an error message.
+  fn display_discriminants(&self) -> String {
+    self.variants.iter().map(|&(ref discriminant, _)| display_value(discriminant)).collect::<Vec<_>>().join(", ")
+  }
+}
+
+impl Schema for SchemaUnion {
+  /// The discriminator property is an enum of every discriminant. Any other
+  /// pointer is the schema of the first variant which defines it.
+  fn get(&self, mut pointer: Pointer) -> Option<&Schema> {
+    if pointer.is_empty() {
+      Some(self)
+    } else if pointer[0] == self.discriminator {
+      pointer.remove(0);
+      self.discriminants.get(pointer)
+    } else {
+      self.variants.iter().filter_map(|&(_, ref variant)| variant.get(pointer.clone())).next()
+    }
+  }
+
+  fn as_any(&self) -> &Any {
+    self
+  }
+
+  fn clone_boxed(&self) -> BoxedSchema {
+    Box::new(self.clone())
+  }
+
+  fn default(&self) -> Option<&SchemaDefault> {
+    self.default.as_ref()
+  }
+
+  fn set_default(&mut self, default: SchemaDefault) {
+    self.default = Some(default);
+  }
+
+  fn references(&self) -> Vec<&str> {
+    self.variants.iter().flat_map(|&(_, ref variant)| variant.references()).collect()
+  }
+
+  fn resolve_references(&mut self, definition: &Definition) {
+    for &mut (_, ref mut variant) in &mut self.variants {
+      variant.resolve_references(definition);
+    }
+  }
+
+  /// A property may be queried if any variant allows the query.
+  fn report_query(&self, report: &mut Report, pointer: Pointer, query: &Query) {
+    if let Query::Keys(ref query_properties) = *query {
+      for (key, sub_query) in query_properties.iter() {
+        let mut property_query = LinearMap::new();
+        property_query.insert(key.to_owned(), sub_query.clone());
+        let property_query = Query::Keys(property_query);
+        self.report_any_variant(report, key, |variant, variant_report| {
+          variant.report_query(variant_report, pointer.clone(), &property_query)
+        });
+      }
+    }
+  }
+
+  fn report_value(&self, report: &mut Report, pointer: Pointer, value: &Value) {
+    let discriminant = match *value {
+      Value::Object(ref object) => object.get(&self.discriminator),
+      _ => return report.add(pointer, "type", invalid_type("object", value))
+    };
+
+    match discriminant.map(|discriminant| (discriminant, self.variant(discriminant))) {
+      Some((_, Some(variant))) => variant.report_value(report, pointer, value),
+      Some((discriminant, None)) => {
+        let mut sub_pointer = pointer;
+        sub_pointer.push(self.discriminator.clone());
+        report.add(sub_pointer, "discriminator", Error::invalid(
+          format!("Value {} of discriminator property \"{}\" does not pick a variant.", display_value(discriminant), self.discriminator),
+          format!("Try using one of the following values: {}.", self.display_discriminants())
+        ));
+      },
+      None => report.add(pointer, "discriminator", Error::invalid(
+        format!("Object is missing discriminator property \"{}\".", self.discriminator),
+        format!("Try adding a value for the \"{}\" property like one of: {}.", self.discriminator, self.display_discriminants())
+      ))
+    }
+  }
+}
 
 #[cfg(test)]
 mod tests {
-  use schema::Schema;
-  use query::Query;
+  use regex::Regex;
+
+  use schema::{Definition, Type, Schema, BoxedSchema, SchemaNumber, SchemaObject, SchemaString, SchemaReference, SchemaUnion, SchemaDefault, Generator, StringFormat};
+  use query::{Condition, SortRule, Query};
+  use value::Value;
 
   #[test]
   fn test_schema_equality() {
@@ -614,4 +1903,689 @@ mod tests {
       str!("moon") => Query::All
     })).is_ok());
   }
+
+  #[test]
+  fn test_value_primitive() {
+    assert!(Schema::none().validate_value(&value!({ "hello" => [1, 2, 3] })).is_ok());
+    assert!(Schema::null().validate_value(&value!()).is_ok());
+    Schema::null().validate_value(&value!(false)).unwrap_err().expect("not of type null");
+    assert!(Schema::boolean().validate_value(&value!(true)).is_ok());
+    Schema::boolean().validate_value(&value!(42)).unwrap_err().expect("not of type boolean");
+    Schema::string().validate_value(&value!(2)).unwrap_err().expect("not of type string");
+    Schema::number().validate_value(&value!("2")).unwrap_err().expect("not of type number");
+  }
+
+  #[test]
+  fn test_value_number() {
+    let mut number = Schema::number();
+    number.set_multiple_of(1.1);
+    number.set_minimum(2.2);
+    number.set_maximum(9.9);
+    number.enable_exclusive_maximum();
+    assert!(number.validate_value(&value!(2.2)).is_ok());
+    assert!(number.validate_value(&value!(3.3)).is_ok());
+    assert!(number.validate_value(&value!(8.8)).is_ok());
+    number.validate_value(&value!(1.1)).unwrap_err().expect("less than the minimum");
+    number.validate_value(&value!(9.9)).unwrap_err().expect("greater than the maximum");
+    number.validate_value(&value!(4)).unwrap_err().expect("not a multiple of");
+    let mut even = Schema::number();
+    even.set_multiple_of(2.0);
+    assert!(even.validate_value(&value!(1_000_000)).is_ok());
+    even.validate_value(&value!(1_000_001)).unwrap_err().expect("not a multiple of");
+    even.validate_value(&value!(1_000_001.0)).unwrap_err().expect("not a multiple of");
+    let mut integer = Schema::number();
+    integer.set_multiple_of(1.0);
+    integer.set_minimum(8.0);
+    integer.enable_exclusive_minimum();
+    assert!(integer.validate_value(&value!(9)).is_ok());
+    assert!(integer.validate_value(&value!(9.0)).is_ok());
+    integer.validate_value(&value!(8)).unwrap_err().expect("less than the minimum");
+    integer.validate_value(&value!(9.5)).unwrap_err().expect("not a multiple of");
+    let mut integer = Schema::integer();
+    integer.set_maximum(10.0);
+    assert!(integer.integer());
+    assert!(!Schema::number().integer());
+    assert!(integer.validate_value(&value!(-3)).is_ok());
+    assert!(integer.validate_value(&value!(4.0)).is_ok());
+    integer.validate_value(&value!(4.5)).unwrap_err().expect("Value 4.5 is not of type integer.");
+    integer.validate_value(&value!("4")).unwrap_err().expect("is not of type integer");
+    let mut large = Schema::integer();
+    large.set_multiple_of(2.0);
+    large.set_maximum(9007199254740992.0);
+    assert!(large.validate_value(&value!(9007199254740992i64)).is_ok());
+    large.validate_value(&value!(9007199254740993i64)).unwrap_err().expect("Validation failed in 2 places. Number 9007199254740993 is not a multiple of 2. Number 9007199254740993 is greater than the maximum");
+  }
+
+  #[test]
+  fn test_value_string() {
+    let mut string = Schema::string();
+    string.set_min_length(4);
+    string.set_max_length(8);
+    string.set_pattern(Regex::new(r"^[a-z]+$").unwrap());
+    assert!(string.validate_value(&value!("hello")).is_ok());
+    string.validate_value(&value!("yo")).unwrap_err().expect("shorter than the minimum length of 4");
+    string.validate_value(&value!("goodbyeworld")).unwrap_err().expect("longer than the maximum length of 8");
+    string.validate_value(&value!("HELLO")).unwrap_err().expect("does not match the pattern");
+  }
+
+  #[test]
+  fn test_value_string_format() {
+    let valid = |format, string: &str| {
+      let mut schema = Schema::string();
+      schema.set_format(format);
+      schema.validate_value(&value!(string)).is_ok()
+    };
+    assert!(valid(StringFormat::Email, "ada@example.com"));
+    assert!(!valid(StringFormat::Email, "ada@example"));
+    assert!(!valid(StringFormat::Email, "ada lovelace@example.com"));
+    assert!(valid(StringFormat::Uri, "https://example.com/a?b=c"));
+    assert!(!valid(StringFormat::Uri, "/relative/path"));
+    assert!(valid(StringFormat::DateTime, "2016-05-01T12:00:00Z"));
+    assert!(valid(StringFormat::DateTime, "2016-05-01T12:00:00.5+02:00"));
+    assert!(!valid(StringFormat::DateTime, "2016-05-01"));
+    assert!(valid(StringFormat::Date, "2016-02-29"));
+    assert!(!valid(StringFormat::Date, "2015-02-29"));
+    assert!(!valid(StringFormat::Date, "2016-5-1"));
+    assert!(!valid(StringFormat::Date, "20160-05-01"));
+    assert!(valid(StringFormat::Uuid, "8a7c3b2e-6d4f-4e1a-9b0c-1d2e3f4a5b6c"));
+    assert!(!valid(StringFormat::Uuid, "8a7c3b2e6d4f4e1a9b0c1d2e3f4a5b6c"));
+    assert!(valid(StringFormat::Ipv4, "127.0.0.1"));
+    assert!(!valid(StringFormat::Ipv4, "256.0.0.1"));
+    assert!(valid(StringFormat::Ipv6, "::1"));
+    assert!(!valid(StringFormat::Ipv6, "127.0.0.1"));
+    let mut email = Schema::string();
+    email.set_format(StringFormat::Email);
+    email.validate_value(&value!("hello")).unwrap_err().expect("String \"hello\" is not a valid email.");
+    assert_eq!(StringFormat::from_name("date-time"), Some(StringFormat::DateTime));
+    assert_eq!(StringFormat::from_name("party"), None);
+  }
+
+  #[test]
+  fn test_value_array() {
+    let array_none = Schema::array();
+    let mut array_bool = Schema::array();
+    array_bool.set_items(Schema::boolean());
+    assert!(array_none.validate_value(&value!([1, "two", ()])).is_ok());
+    assert!(array_bool.validate_value(&value!([])).is_ok());
+    assert!(array_bool.validate_value(&value!([true, false])).is_ok());
+    array_bool.validate_value(&value!([true, 2])).unwrap_err().expect("not of type boolean");
+    array_bool.validate_value(&value!({ "0" => true })).unwrap_err().expect("not of type array");
+    let mut array_sized = Schema::array();
+    array_sized.set_min_items(1);
+    array_sized.set_max_items(2);
+    assert!(array_sized.validate_value(&value!([1])).is_ok());
+    assert!(array_sized.validate_value(&value!([1, 2])).is_ok());
+    array_sized.validate_value(&value!([])).unwrap_err().expect("0 items which is fewer than the minimum of 1");
+    array_sized.validate_value(&value!([1, 2, 3])).unwrap_err().expect("3 items which is more than the maximum of 2");
+  }
+
+  #[test]
+  fn test_value_object() {
+    let mut object = Schema::object();
+    object.set_required(vec!["hello"]);
+    object.add_property("hello", Schema::boolean());
+    object.add_property("goodbye", {
+      let mut goodbye = Schema::object();
+      goodbye.add_property("world", Schema::string());
+      goodbye
+    });
+    let mut object_additional = Schema::object();
+    object_additional.enable_additional_properties();
+    object_additional.add_property("hello", Schema::boolean());
+    assert!(object.validate_value(&value!({ "hello" => true })).is_ok());
+    assert!(object.validate_value(&value!({ "hello" => true, "goodbye" => { "world" => "moon" } })).is_ok());
+    object.validate_value(&value!({ "goodbye" => {} })).unwrap_err().expect("missing required property \"hello\"");
+    object.validate_value(&value!({ "hello" => true, "moon" => 2 })).unwrap_err().expect("property \"moon\" is not allowed");
+    object.validate_value(&value!({ "hello" => true, "goodbye" => { "world" => 2 } })).unwrap_err().expect("not of type string");
+    object.validate_value(&value!([true])).unwrap_err().expect("not of type object");
+    assert!(object_additional.validate_value(&value!({ "hello" => false, "moon" => 2 })).is_ok());
+    object_additional.validate_value(&value!({ "hello" => 2 })).unwrap_err().expect("not of type boolean");
+  }
+
+  fn get_map_schema() -> SchemaObject {
+    let mut object = Schema::object();
+    object.add_property("title", Schema::string());
+    object.add_property("translations", {
+      let mut translations = Schema::object();
+      translations.add_pattern_property(Regex::new(r"^[a-z]{2}$").unwrap(), Schema::string());
+      translations
+    });
+    object.add_property("counts", {
+      let mut counts = Schema::object();
+      counts.add_property("total", Schema::integer());
+      counts.set_additional_properties({
+        let mut count = Schema::object();
+        count.add_property("value", Schema::number());
+        count
+      });
+      counts
+    });
+    object
+  }
+
+  #[test]
+  fn test_get_map() {
+    let object = get_map_schema();
+    assert!(object.get(point!["translations", "en"]).unwrap().eq(&Schema::string()));
+    assert!(object.get(point!["translations", "english"]).is_none());
+    assert!(object.get(point!["counts", "total"]).unwrap().eq(&Schema::integer()));
+    assert!(object.get(point!["counts", "views", "value"]).unwrap().eq(&Schema::number()));
+    assert!(object.get(point!["counts", "views", "other"]).is_none());
+  }
+
+  #[test]
+  fn test_query_map() {
+    let object = get_map_schema();
+    assert!(object.validate_query(&Query::Keys(linear_map! {
+      str!("translations") => Query::Keys(linear_map! {
+        str!("en") => Query::All,
+        str!("fr") => Query::All
+      }),
+      str!("counts") => Query::Keys(linear_map! {
+        str!("total") => Query::All,
+        str!("views") => Query::Keys(linear_map! {
+          str!("value") => Query::All
+        })
+      })
+    })).is_ok());
+    object.validate_query(&Query::Keys(linear_map! {
+      str!("translations") => Query::Keys(linear_map! {
+        str!("english") => Query::All
+      })
+    })).unwrap_err().expect("Cannot query object property \"english\".");
+    object.validate_query(&Query::Keys(linear_map! {
+      str!("translations") => Query::Keys(linear_map! {
+        str!("en") => Query::Keys(linear_map! {})
+      })
+    })).unwrap_err().expect("deeply query");
+    object.validate_query(&Query::Keys(linear_map! {
+      str!("counts") => Query::Keys(linear_map! {
+        str!("views") => Query::Keys(linear_map! {
+          str!("other") => Query::All
+        })
+      })
+    })).unwrap_err().expect("Cannot query object property \"other\".");
+  }
+
+  #[test]
+  fn test_value_map() {
+    let object = get_map_schema();
+    assert!(object.validate_value(&value!({
+      "translations" => { "en" => "Hello", "fr" => "Bonjour" },
+      "counts" => { "total" => 2, "views" => { "value" => 1.5 } }
+    })).is_ok());
+    object.validate_value(&value!({ "translations" => { "english" => "Hello" } })).unwrap_err().expect("property \"english\" is not allowed");
+    object.validate_value(&value!({ "translations" => { "en" => 5 } })).unwrap_err().expect("not of type string");
+    object.validate_value(&value!({ "counts" => { "views" => 5 } })).unwrap_err().expect("not of type object");
+    object.validate_value(&value!({ "counts" => { "total" => { "value" => 5 } } })).unwrap_err().expect("not of type integer");
+    // A property must be valid against every pattern property it matches.
+    let mut tags = Schema::object();
+    tags.add_pattern_property(Regex::new("^[a-z]+$").unwrap(), {
+      let mut tag = Schema::string();
+      tag.set_max_length(3);
+      tag
+    });
+    tags.add_pattern_property(Regex::new("^a").unwrap(), {
+      let mut tag = Schema::string();
+      tag.set_min_length(2);
+      tag
+    });
+    assert!(tags.validate_value(&value!({ "abc" => "ab", "b" => "x", "a1" => "abcd" })).is_ok());
+    tags.validate_value(&value!({ "abc" => "a" })).unwrap_err().expect("String \"a\" is shorter than the minimum length of 2.");
+    tags.validate_value(&value!({ "abc" => "abcd" })).unwrap_err().expect("String \"abcd\" is longer than the maximum length of 3.");
+    assert_eq!(tags.get_property_schemas("abc").len(), 2);
+    assert_eq!(tags.get_property_schemas("a1").len(), 1);
+  }
+
+  #[test]
+  fn test_value_enum() {
+    let enum_ = Schema::enum_(vec![value!("red"), value!(2), value!({ "hello" => "world" })]);
+    assert!(enum_.validate_value(&value!("red")).is_ok());
+    assert!(enum_.validate_value(&value!(2)).is_ok());
+    assert!(enum_.validate_value(&value!({ "hello" => "world" })).is_ok());
+    enum_.validate_value(&value!("blue")).unwrap_err().expect("not one of the enumerated values");
+    enum_.validate_value(&value!(2.0)).unwrap_err().expect("not one of the enumerated values");
+  }
+
+  #[test]
+  fn test_report_all_failures() {
+    let mut object = Schema::object();
+    object.set_required(vec!["hello", "world"]);
+    object.add_property("hello", Schema::boolean());
+    object.add_property("goodbye", {
+      let mut goodbye = Schema::array();
+      goodbye.set_items({
+        let mut string = Schema::string();
+        string.set_min_length(3);
+        string.set_pattern(Regex::new(r"^[a-z]+$").unwrap());
+        string
+      });
+      goodbye
+    });
+    let error = object.validate_value(&value!({
+      "hello" => 2,
+      "goodbye" => ["abc", "A", 5],
+      "moon" => true
+    })).unwrap_err();
+    error.expect("Validation failed in 6 places.");
+    assert_eq!(
+      error.failures().iter().map(|failure| (failure.pointer().clone(), failure.keyword())).collect::<Vec<_>>(),
+      vec![
+        (point![], "required"),
+        (point!["hello"], "type"),
+        (point!["goodbye", "1"], "minLength"),
+        (point!["goodbye", "1"], "pattern"),
+        (point!["goodbye", "2"], "type"),
+        (point!["moon"], "additionalProperties")
+      ]
+    );
+    let error = object.validate_query(&Query::Keys(linear_map! {
+      str!("moon") => Query::All,
+      str!("hello") => Query::Keys(linear_map! {}),
+      str!("goodbye") => Query::Keys(linear_map! {
+        str!("yo") => Query::All
+      })
+    })).unwrap_err();
+    error.expect("Validation failed in 3 places.");
+    assert_eq!(
+      error.failures().iter().map(|failure| (failure.pointer().clone(), failure.keyword())).collect::<Vec<_>>(),
+      vec![
+        (point!["moon"], "properties"),
+        (point!["hello"], "type"),
+        (point!["goodbye", "yo"], "items")
+      ]
+    );
+  }
+
+  #[test]
+  fn test_downcast_ref() {
+    let number: Box<Schema> = Box::new(Schema::number());
+    assert!(number.downcast_ref::<SchemaNumber>().is_some());
+    assert!(number.downcast_ref::<SchemaString>().is_none());
+  }
+
+  fn get_condition_schema() -> SchemaObject {
+    let mut object = Schema::object();
+    object.add_property("name", Schema::string());
+    object.add_property("age", Schema::number());
+    object.add_property("alive", Schema::boolean());
+    object.add_property("tags", {
+      let mut tags = Schema::array();
+      tags.set_items(Schema::string());
+      tags
+    });
+    object.add_property("anything", Schema::none());
+    object.add_property("address", {
+      let mut address = Schema::object();
+      address.enable_additional_properties();
+      address.add_property("city", Schema::string());
+      address
+    });
+    object
+  }
+
+  #[test]
+  fn test_condition() {
+    let object = get_condition_schema();
+    assert!(object.validate_condition(&Condition::True).is_ok());
+    assert!(object.validate_condition(&Condition::Keys(linear_map! {
+      str!("name") => Condition::Matches(Regex::new("^a").unwrap()),
+      str!("age") => Condition::And(vec![
+        Condition::GreaterThan(Value::I64(18)),
+        Condition::Not(Box::new(Condition::In(vec![Value::I64(21), Value::F64(30.5)])))
+      ]),
+      str!("alive") => Condition::Equal(Value::Null),
+      str!("tags") => Condition::Contains(Value::String(str!("red"))),
+      str!("anything") => Condition::Keys(linear_map! {
+        str!("deep") => Condition::LessThan(Value::Boolean(true))
+      }),
+      str!("address") => Condition::Keys(linear_map! {
+        str!("city") => Condition::Exists,
+        str!("zip") => Condition::Equal(Value::I64(12345))
+      })
+    })).is_ok());
+    object.validate_condition(&Condition::Keys(linear_map! {
+      str!("moon") => Condition::Exists
+    })).unwrap_err().expect("Object property \"moon\" is not defined");
+    object.validate_condition(&Condition::Keys(linear_map! {
+      str!("name") => Condition::Equal(Value::I64(5))
+    })).unwrap_err().expect("Cannot compare a property of type string with 5.");
+    object.validate_condition(&Condition::Keys(linear_map! {
+      str!("alive") => Condition::GreaterThan(Value::Boolean(false))
+    })).unwrap_err().expect("Cannot order a property of type boolean.");
+    object.validate_condition(&Condition::Keys(linear_map! {
+      str!("age") => Condition::Matches(Regex::new("1").unwrap())
+    })).unwrap_err().expect("Cannot match a pattern against a property of type number.");
+    object.validate_condition(&Condition::Keys(linear_map! {
+      str!("tags") => Condition::Contains(Value::I64(1))
+    })).unwrap_err().expect("Cannot compare a property of type string with 1.");
+    object.validate_condition(&Condition::Keys(linear_map! {
+      str!("name") => Condition::Contains(Value::String(str!("a")))
+    })).unwrap_err().expect("Cannot check if a property of type string contains a value.");
+    object.validate_condition(&Condition::Keys(linear_map! {
+      str!("age") => Condition::Keys(linear_map! {
+        str!("years") => Condition::Exists
+      })
+    })).unwrap_err().expect("Cannot use property \"years\" of a primitive value.");
+    object.validate_condition(&Condition::Keys(linear_map! {
+      str!("tags") => Condition::Keys(linear_map! {
+        str!("first") => Condition::Exists
+      })
+    })).unwrap_err().expect("Cannot use non-integer \"first\" array property.");
+    let error = object.validate_condition(&Condition::Or(vec![
+      Condition::Keys(linear_map! {
+        str!("moon") => Condition::Exists
+      }),
+      Condition::Keys(linear_map! {
+        str!("age") => Condition::In(vec![Value::String(str!("one")), Value::I64(2)]),
+        str!("tags") => Condition::Keys(linear_map! {
+          str!("0") => Condition::Equal(Value::Boolean(true))
+        })
+      })
+    ])).unwrap_err();
+    error.expect("Validation failed in 3 places.");
+    assert_eq!(
+      error.failures().iter().map(|failure| (failure.pointer().clone(), failure.keyword())).collect::<Vec<_>>(),
+      vec![
+        (point!["moon"], "properties"),
+        (point!["age"], "type"),
+        (point!["tags", "0"], "type")
+      ]
+    );
+  }
+
+  #[test]
+  fn test_sort() {
+    let object = get_condition_schema();
+    assert!(object.validate_sort(&[]).is_ok());
+    assert!(object.validate_sort(&[
+      SortRule::new(point!["name"], true),
+      SortRule::new(point!["age"], false),
+      SortRule::new(point!["tags", "0"], true),
+      SortRule::new(point!["anything", "deep"], true),
+      SortRule::new(point!["address", "zip"], true)
+    ]).is_ok());
+    object.validate_sort(&[SortRule::new(point!["moon"], true)]).unwrap_err().expect("Object property \"moon\" is not defined");
+    object.validate_sort(&[SortRule::new(point!["address", "city", "length"], true)]).unwrap_err().expect("Cannot use property \"length\" of a primitive value.");
+    object.validate_sort(&[SortRule::new(point!["tags"], true)]).unwrap_err().expect("Cannot sort by a property of type array.");
+  }
+
+  fn get_reference_definition() -> Definition {
+    let mut definition = Definition::new();
+    definition.add_type("person", {
+      let mut person = Type::new();
+      person.set_required(vec!["name"]);
+      person.add_property("id", Schema::string());
+      person.add_property("name", Schema::string());
+      person
+    });
+    definition.add_type("post", {
+      let mut post = Type::new();
+      post.add_property("headline", Schema::string());
+      post.add_property("author", Schema::reference("person"));
+      post.add_property("editors", {
+        let mut editors = Schema::array();
+        editors.set_items(Schema::reference("person"));
+        editors
+      });
+      post
+    });
+    definition
+  }
+
+  #[test]
+  fn test_reference() {
+    let mut definition = get_reference_definition();
+    Schema::reference("person").validate_value(&value!({})).unwrap_err().expect("has not been resolved");
+    definition.resolve_references().unwrap();
+    let post = definition.get_type(&str!("post")).unwrap().schema();
+    assert_eq!(post.references(), vec!["person", "person"]);
+    assert!(post.get(point!["author"]).unwrap().downcast_ref::<SchemaObject>().is_some());
+    assert!(post.properties().get("author").unwrap().downcast_ref::<SchemaReference>().is_some());
+    assert!(post.get(point!["author", "name"]).unwrap().eq(&Schema::string()));
+    assert!(post.get(point!["editors", "0", "name"]).unwrap().eq(&Schema::string()));
+    assert!(post.validate_query(&Query::Keys(linear_map! {
+      str!("author") => Query::Keys(linear_map! {
+        str!("name") => Query::All
+      })
+    })).is_ok());
+    post.validate_query(&Query::Keys(linear_map! {
+      str!("author") => Query::Keys(linear_map! {
+        str!("email") => Query::All
+      })
+    })).unwrap_err().expect("Cannot query object property \"email\".");
+    assert!(post.validate_value(&value!({ "author" => { "name" => "Ada" }, "editors" => [{ "name" => "Alan" }] })).is_ok());
+    assert!(post.validate_value(&value!({ "author" => "ada", "editors" => ["alan"] })).is_ok());
+    post.validate_value(&value!({ "editors" => [{}] })).unwrap_err().expect("missing required property \"name\"");
+    post.validate_value(&value!({ "author" => true })).unwrap_err().expect("Value true is not of type string.");
+    post.validate_value(&value!({ "editors" => ["alan", 2] })).unwrap_err().expect("Value 2 is not of type string.");
+    assert!(post.validate_condition(&Condition::Keys(linear_map! {
+      str!("author") => Condition::Equal(Value::String(str!("ada"))),
+      str!("editors") => Condition::Contains(Value::String(str!("alan")))
+    })).is_ok());
+    post.validate_condition(&Condition::Keys(linear_map! {
+      str!("author") => Condition::Equal(Value::I64(5))
+    })).unwrap_err().expect("Cannot compare a property of type string with 5.");
+    post.validate_condition(&Condition::Keys(linear_map! {
+      str!("editors") => Condition::Contains(Value::I64(2))
+    })).unwrap_err().expect("Cannot compare a property of type string with 2.");
+    post.validate_condition(&Condition::Keys(linear_map! {
+      str!("author") => Condition::Keys(linear_map! {
+        str!("name") => Condition::Equal(Value::String(str!("Ada")))
+      })
+    })).unwrap_err().expect("Cannot use property \"name\" of a reference to type 'person'.");
+    assert!(post.validate_sort(&[SortRule::new(point!["author"], true)]).is_ok());
+    post.validate_sort(&[SortRule::new(point!["author", "name"], true)]).unwrap_err().expect("Cannot use property \"name\" of a reference to type 'person'.");
+  }
+
+  #[test]
+  fn test_reference_dangling() {
+    let mut definition = get_reference_definition();
+    definition.add_type("comment", {
+      let mut comment = Type::new();
+      comment.add_property("post", Schema::reference("article"));
+      comment
+    });
+    definition.resolve_references().unwrap_err().expect("Type 'comment' references type 'article' which is not defined.");
+  }
+
+  #[test]
+  fn test_reference_cycle() {
+    let mut definition = get_reference_definition();
+    definition.add_type("person", {
+      let mut person = Type::new();
+      person.add_property("best_post", Schema::reference("post"));
+      person
+    });
+    definition.resolve_references().unwrap_err().expect("cycle: person -> post -> person.");
+  }
+
+  #[test]
+  fn test_fill_defaults() {
+    let mut object = Schema::object();
+    object.add_boxed_property("id", {
+      let mut id: BoxedSchema = Box::new(Schema::string());
+      id.set_default(SchemaDefault::Generated(Generator::Uuid));
+      id
+    });
+    object.add_property("title", Schema::string());
+    object.add_boxed_property("published", {
+      let mut published: BoxedSchema = Box::new(Schema::boolean());
+      published.set_default(SchemaDefault::Value(value!(false)));
+      published
+    });
+    object.add_boxed_property("meta", {
+      let mut meta = Schema::object();
+      meta.add_boxed_property("views", {
+        let mut views: BoxedSchema = Box::new(Schema::number());
+        views.set_default(SchemaDefault::Value(value!(0)));
+        views
+      });
+      let mut meta: BoxedSchema = Box::new(meta);
+      meta.set_default(SchemaDefault::Value(value!({})));
+      meta
+    });
+    let mut value = value!({ "title" => "Hello", "published" => true });
+    object.fill_defaults(&mut value);
+    assert_eq!(value.get(point!["title"]), Some(&value!("Hello")));
+    assert_eq!(value.get(point!["published"]), Some(&value!(true)));
+    assert_eq!(value.get(point!["meta"]), Some(&value!({ "views" => 0 })));
+    assert!(object.validate_value(&value).is_ok());
+    let id = value.get(point!["id"]).cloned().unwrap();
+    let mut other_value = value!({});
+    object.fill_defaults(&mut other_value);
+    assert!(other_value.get(point!["id"]).unwrap() != &id);
+    assert_eq!(other_value.get(point!["published"]), Some(&value!(false)));
+    let mut string_value = value!("hello");
+    object.fill_defaults(&mut string_value);
+    assert_eq!(string_value, value!("hello"));
+  }
+
+  fn get_union() -> SchemaUnion {
+    let mut payload = Schema::union("kind");
+    payload.add_variant("click", {
+      let mut click = Schema::object();
+      click.set_required(vec!["x"]);
+      click.add_property("x", Schema::number());
+      click.add_property("y", Schema::number());
+      click
+    });
+    payload.add_variant("key", {
+      let mut key = Schema::object();
+      key.add_property("code", Schema::string());
+      key.add_property("modifiers", {
+        let mut modifiers = Schema::array();
+        modifiers.set_items(Schema::string());
+        modifiers
+      });
+      key
+    });
+    payload
+  }
+
+  #[test]
+  fn test_union_get() {
+    let payload = get_union();
+    assert_eq!(payload.discriminator(), "kind");
+    assert!(payload.variant(&value!("click")).unwrap().get(point!["x"]).unwrap().eq(&Schema::number()));
+    assert!(payload.variant(&value!("key")).unwrap().get(point!["x"]).is_none());
+    assert!(payload.variant(&value!("scroll")).is_none());
+    assert_eq!(payload.variant(&value!("key")).unwrap().required(), &vec![str!("kind")]);
+    // Without a discriminant the properties of every variant are found.
+    assert!(payload.get(point!["x"]).unwrap().eq(&Schema::number()));
+    assert!(payload.get(point!["modifiers", "0"]).unwrap().eq(&Schema::string()));
+    assert!(payload.get(point!["kind"]).unwrap().eq(&Schema::enum_(vec!["click", "key"])));
+    assert!(payload.get(point!["kind", "a"]).is_none());
+    assert!(payload.get(point!["z"]).is_none());
+  }
+
+  #[test]
+  fn test_union_query() {
+    let payload = get_union();
+    assert!(payload.validate_query(&Query::All).is_ok());
+    assert!(payload.validate_query(&Query::Keys(linear_map! {
+      str!("kind") => Query::All,
+      str!("x") => Query::All,
+      str!("modifiers") => Query::Keys(linear_map! { str!("0") => Query::All })
+    })).is_ok());
+    payload.validate_query(&Query::Keys(linear_map! {
+      str!("z") => Query::All
+    })).unwrap_err().expect("Cannot query object property \"z\".");
+    payload.validate_query(&Query::Keys(linear_map! {
+      str!("code") => Query::Keys(linear_map! { str!("a") => Query::All })
+    })).unwrap_err().expect("Cannot deeply query a primitive value.");
+    // Pinned to a variant, only the properties of that variant are allowed.
+    let x_query = Query::Keys(linear_map! { str!("x") => Query::All });
+    let pin = |kind: &str| Condition::Keys(linear_map! { str!("kind") => Condition::Equal(value!(kind)) });
+    assert!(payload.validate_pinned_query(&x_query, &Condition::True).is_ok());
+    assert!(payload.validate_pinned_query(&x_query, &pin("click")).is_ok());
+    payload.validate_pinned_query(&x_query, &pin("key")).unwrap_err().expect("Cannot query object property \"x\".");
+    payload.validate_pinned_query(&x_query, &Condition::And(vec![Condition::True, pin("key")])).unwrap_err().expect("Cannot query object property \"x\".");
+    let mut event = Schema::object();
+    event.add_property("payload", get_union());
+    let payload_query = Query::Keys(linear_map! { str!("payload") => x_query.clone() });
+    assert!(event.validate_pinned_query(&payload_query, &Condition::True).is_ok());
+    event.validate_pinned_query(&payload_query, &Condition::Keys(linear_map! { str!("payload") => pin("key") })).unwrap_err().expect("Cannot query object property \"x\".");
+    event.validate_pinned_query(&Query::Keys(linear_map! { str!("z") => Query::All }), &Condition::True).unwrap_err().expect("Cannot query object property \"z\".");
+  }
+
+  #[test]
+  fn test_union_value() {
+    let payload = get_union();
+    assert!(payload.validate_value(&value!({ "kind" => "click", "x" => 1, "y" => 2 })).is_ok());
+    assert!(payload.validate_value(&value!({ "kind" => "key", "code" => "Enter", "modifiers" => ["Shift"] })).is_ok());
+    payload.validate_value(&value!({ "kind" => "click", "y" => 2 })).unwrap_err().expect("missing required property \"x\"");
+    payload.validate_value(&value!({ "kind" => "key", "x" => 1 })).unwrap_err().expect("property \"x\" is not allowed");
+    payload.validate_value(&value!({ "kind" => "scroll" })).unwrap_err().expect("Value \"scroll\" of discriminator property \"kind\" does not pick a variant.");
+    payload.validate_value(&value!({ "x" => 1 })).unwrap_err().expect("missing discriminator property \"kind\"");
+    payload.validate_value(&value!("click")).unwrap_err().expect("not of type object");
+  }
+
+  #[test]
+  fn test_union_condition() {
+    let mut event = Schema::object();
+    event.add_property("payload", get_union());
+    assert!(event.validate_condition(&Condition::Keys(linear_map! {
+      str!("payload") => Condition::Keys(linear_map! {
+        str!("x") => Condition::GreaterThan(value!(4)),
+        str!("code") => Condition::Equal(value!("Enter"))
+      })
+    })).is_ok());
+    event.validate_condition(&Condition::Keys(linear_map! {
+      str!("payload") => Condition::Keys(linear_map! {
+        str!("z") => Condition::Equal(value!(4))
+      })
+    })).unwrap_err().expect("not defined in any variant");
+    event.validate_condition(&Condition::Keys(linear_map! {
+      str!("payload") => Condition::Keys(linear_map! {
+        str!("kind") => Condition::Equal(value!("key")),
+        str!("x") => Condition::GreaterThan(value!(4))
+      })
+    })).unwrap_err().expect("Object property \"x\" is not defined in the schema.");
+    // Variants may give the same property different types.
+    let mut shape = Schema::union("kind");
+    shape.add_variant("circle", {
+      let mut circle = Schema::object();
+      circle.add_property("size", Schema::number());
+      circle
+    });
+    shape.add_variant("label", {
+      let mut label = Schema::object();
+      label.add_property("size", Schema::string());
+      label
+    });
+    assert!(shape.validate_condition(&Condition::Keys(linear_map! { str!("size") => Condition::Equal(value!(2)) })).is_ok());
+    assert!(shape.validate_condition(&Condition::Keys(linear_map! { str!("size") => Condition::Equal(value!("large")) })).is_ok());
+    shape.validate_condition(&Condition::Keys(linear_map! { str!("size") => Condition::Equal(value!(true)) })).unwrap_err().expect("Cannot compare a property of type number with true.");
+    assert!(event.validate_sort(&[SortRule::new(point!["payload", "x"], true)]).is_ok());
+    event.validate_sort(&[SortRule::new(point!["payload"], true)]).unwrap_err().expect("Cannot sort by a property of type object.");
+    assert!(event.validate_sort(&[SortRule::new(point!["payload", "kind"], true)]).is_ok());
+    // A nested property is found in any variant, not only the first one
+    // defining its parent.
+    let mut message = Schema::union("kind");
+    message.add_variant("number", {
+      let mut number = Schema::object();
+      number.add_property("data", {
+        let mut data = Schema::object();
+        data.add_property("a", Schema::number());
+        data
+      });
+      number
+    });
+    message.add_variant("text", {
+      let mut text = Schema::object();
+      text.add_property("data", {
+        let mut data = Schema::object();
+        data.add_property("b", Schema::string());
+        data
+      });
+      text
+    });
+    let mut inbox = Schema::object();
+    inbox.add_property("message", message);
+    assert!(inbox.validate_sort(&[SortRule::new(point!["message", "data", "a"], true)]).is_ok());
+    assert!(inbox.validate_sort(&[SortRule::new(point!["message", "data", "b"], true)]).is_ok());
+    inbox.validate_sort(&[SortRule::new(point!["message", "data", "c"], true)]).unwrap_err().expect("Object property \"c\" is not defined in the schema.");
+    inbox.validate_sort(&[SortRule::new(point!["message", "z"], true)]).unwrap_err().expect("not defined in any variant");
+  }
 }
@@ -0,0 +1,166 @@
+//! The entry point for using an Ardite Schema Definition. A service owns a
+//! definition along with the drivers for its types, and checks every request
+//! against the schema before it is sent to a driver.
+
+use driver::{Driver, DriverRegistry, DriverRouter, read_related};
+use error::{Error, NotFound};
+use query::{Condition, SortRule, Range, Query};
+use schema::{Definition, Type};
+use value::{Value, ValueIter};
+
+/// A definition and the connected drivers for every one of its types.
+/// Requests refer to types by name.
+pub struct Service {
+  definition: Definition,
+  driver: DriverRouter
+}
+
+impl Service {
+  /// Creates a new service from a definition and a router which has a driver
+  /// for every type in the definition.
+  pub fn new(definition: Definition, driver: DriverRouter) -> Self {
+    Service {
+      definition: definition,
+      driver: driver
+    }
+  }
+
+  /// Creates a new service by connecting the drivers for every type in the
+  /// definition with a registry.
+  pub fn connect(definition: Definition, registry: &DriverRegistry) -> Result<Self, Error> {
+    let driver = try!(registry.resolve(&definition));
+    Ok(Service::new(definition, driver))
+  }
+
+  /// Get the definition.
+  pub fn definition(&self) -> &Definition {
+    &self.definition
+  }
+
+  /// Gets a type by name, or a `NotFound` error if the definition does not
+  /// have a type of that name.
+  pub fn get_type(&self, name: &str) -> Result<&Type, Error> {
+    self.definition.types().get(name).ok_or_else(|| {
+      Error::new(NotFound, format!("Type '{}' is not defined.", name)).set_hint(format!(
+        "Try using one of the defined types: {}.",
+        self.definition.types().keys().map(String::as_str).collect::<Vec<_>>().join(", ")
+      ))
+    })
+  }
+
+  /// Reads some values of a type. The query, condition, and sort rules are
+  /// validated against the schema of the type before the driver for the type
+  /// is called. The query is validated with the condition, so that a union
+  /// pinned to a variant only allows the properties of that variant.
+  /// References selected into by the query are read from the drivers of the
+  /// referenced types with `read_related`.
+  pub fn read(
+    &self,
+    name: &str,
+    condition: Condition,
+    sort: Vec<SortRule>,
+    range: Range,
+    query: Query
+  ) -> Result<ValueIter, Error> {
+    let type_ = try!(self.get_type(name));
+    try!(type_.validate_pinned_query(&query, &condition));
+    try!(type_.validate_condition(&condition));
+    try!(type_.validate_sort(&sort));
+    read_related(&self.driver, &self.definition, type_, condition, sort, range, query)
+  }
+
+  /// Creates some values of a type. Missing properties with a default in the
+  /// schema are filled in, and then every value is validated against the
+  /// schema before the driver for the type is called. Returns the values as
+  /// they were created.
+  pub fn create(&self, name: &str, mut values: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let type_ = try!(self.get_type(name));
+    for value in &mut values {
+      type_.fill_defaults(value);
+      try!(type_.validate_value(value));
+    }
+    self.driver.create(type_, values)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use url::Url;
+
+  use driver::{Driver, DriverRegistry};
+  use error::{NotFound, BadRequest};
+  use query::{Condition, SortRule, Query};
+  use schema::{Definition, Type, DriverConfig, Schema, BoxedSchema, SchemaDefault, Generator};
+  use service::Service;
+  use value::Value;
+
+  fn get_service() -> Service {
+    let mut definition = Definition::new();
+    definition.set_driver(DriverConfig::new(Url::parse("memory:").unwrap()));
+    definition.add_type("person", {
+      let mut person = Type::new();
+      person.add_boxed_property("id", {
+        let mut id: BoxedSchema = Box::new(Schema::string());
+        id.set_default(SchemaDefault::Generated(Generator::Uuid));
+        id
+      });
+      person.add_property("name", Schema::string());
+      person.add_property("age", Schema::number());
+      person
+    });
+    let service = Service::connect(definition, &DriverRegistry::with_builtin_drivers()).unwrap();
+    service.driver.create(service.get_type("person").unwrap(), vec![
+      value!({ "name" => "Ada", "age" => 36 }),
+      value!({ "name" => "Alan", "age" => 41 })
+    ]).unwrap();
+    service
+  }
+
+  #[test]
+  fn test_read() {
+    let service = get_service();
+    assert_eq!(
+      service.read(
+        "person",
+        Condition::Keys(linear_map! { str!("age") => Condition::GreaterThan(Value::I64(40)) }),
+        vec![SortRule::new(point!["name"], true)],
+        Default::default(),
+        Query::Keys(linear_map! { str!("name") => Query::All })
+      ).unwrap().collect::<Vec<_>>(),
+      vec![value!({ "name" => "Alan" })]
+    );
+  }
+
+  #[test]
+  fn test_read_unknown_type() {
+    let error = get_service().read("post", Default::default(), vec![], Default::default(), Default::default()).err().unwrap();
+    assert_eq!(error.code(), &NotFound);
+    error.expect("Type 'post' is not defined.");
+    assert_eq!(error.hint(), Some("Try using one of the defined types: person."));
+  }
+
+  #[test]
+  fn test_read_invalid() {
+    let service = get_service();
+    let read = |condition, sort, query| service.read("person", condition, sort, Default::default(), query).err().unwrap();
+    let error = read(Condition::True, vec![], Query::Keys(linear_map! { str!("email") => Query::All }));
+    assert_eq!(error.code(), &BadRequest);
+    error.expect("Cannot query object property \"email\".");
+    read(Condition::Keys(linear_map! { str!("name") => Condition::Equal(Value::I64(5)) }), vec![], Query::All).expect("Cannot compare");
+    read(Condition::True, vec![SortRule::new(point!["email"], true)], Query::All).expect("\"email\" is not defined");
+  }
+
+  #[test]
+  fn test_create() {
+    let service = get_service();
+    let created = service.create("person", vec![value!({ "name" => "Grace", "age" => 85 }), value!({ "id" => "hopper", "name" => "Hopper" })]).unwrap();
+    assert_eq!(created.len(), 2);
+    assert!(created[0].get(point!["id"]).is_some());
+    assert_eq!(created[1].get(point!["id"]), Some(&value!("hopper")));
+    assert_eq!(
+      service.read("person", Condition::Keys(linear_map! { str!("name") => Condition::Equal(value!("Grace")) }), vec![], Default::default(), Default::default()).unwrap().collect::<Vec<_>>(),
+      vec![created[0].clone()]
+    );
+    service.create("person", vec![value!({ "name" => 42 })]).unwrap_err().expect("is not of type string");
+  }
+}
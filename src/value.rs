@@ -3,6 +3,8 @@
 //! changed in the future. Driver authors must cast the data they retrieve from
 //! the driver to these types.
 
+use std::cmp::Ordering;
+
 use linear_map::LinearMap;
 use serde::ser::{Serialize, Serializer};
 use serde::de::{Deserialize, Deserializer, Error as DeError, Visitor, SeqVisitor, MapVisitor};
@@ -83,6 +85,35 @@ impl Value {
     }
   }
 
+  /// Compares two values. Integers and floats are compared numerically,
+  /// strings are compared lexicographically, and arrays are compared item by
+  /// item. Values of different types can not be compared and `None` is
+  /// returned, unless the values are exactly equal.
+  ///
+  /// `PartialOrd` is not implemented because this comparison considers
+  /// `I64(1)` and `F64(1.0)` to be equal, which `PartialEq` does not.
+  pub fn compare(&self, other: &Value) -> Option<Ordering> {
+    match (self, other) {
+      (&Value::Null, &Value::Null) => Some(Ordering::Equal),
+      (&Value::Boolean(a), &Value::Boolean(b)) => a.partial_cmp(&b),
+      (&Value::I64(a), &Value::I64(b)) => a.partial_cmp(&b),
+      (&Value::I64(a), &Value::F64(b)) => (a as f64).partial_cmp(&b),
+      (&Value::F64(a), &Value::I64(b)) => a.partial_cmp(&(b as f64)),
+      (&Value::F64(a), &Value::F64(b)) => a.partial_cmp(&b),
+      (&Value::String(ref a), &Value::String(ref b)) => a.partial_cmp(b),
+      (&Value::Array(ref a), &Value::Array(ref b)) => {
+        for (a_item, b_item) in a.iter().zip(b.iter()) {
+          match a_item.compare(b_item) {
+            Some(Ordering::Equal) => {},
+            ordering => return ordering
+          }
+        }
+        a.len().partial_cmp(&b.len())
+      },
+      _ => if self == other { Some(Ordering::Equal) } else { None }
+    }
+  }
+
   /// Creates a `Value` from a JSON string.
   pub fn from_json(json: &str) -> Result<Value, Error> {
     serde_json::from_str(json).map_err(Error::from)
@@ -322,4 +353,21 @@ mod tests {
       "[\n  \"world\",\n  3.333,\n  {\n    \"hello\": \"world\"\n  },\n  null,\n  null,\n  [\n    1,\n    2,\n    3\n  ],\n  null\n]"
     );
   }
+
+  #[test]
+  fn test_compare() {
+    use std::cmp::Ordering::{Less, Equal, Greater};
+    assert_eq!(value!().compare(&value!()), Some(Equal));
+    assert_eq!(value!(false).compare(&value!(true)), Some(Less));
+    assert_eq!(value!(2).compare(&value!(1)), Some(Greater));
+    assert_eq!(value!(2).compare(&value!(2.5)), Some(Less));
+    assert_eq!(value!(2.0).compare(&value!(2)), Some(Equal));
+    assert_eq!(value!("abc").compare(&value!("abd")), Some(Less));
+    assert_eq!(value!([1, 2]).compare(&value!([1, 2, 3])), Some(Less));
+    assert_eq!(value!([1, 3]).compare(&value!([1, 2, 3])), Some(Greater));
+    assert_eq!(value!({ "a" => 1 }).compare(&value!({ "a" => 1 })), Some(Equal));
+    assert_eq!(value!({ "a" => 1 }).compare(&value!({ "a" => 2 })), None);
+    assert_eq!(value!("1").compare(&value!(1)), None);
+    assert_eq!(value!().compare(&value!(false)), None);
+  }
 }
@@ -9,6 +9,7 @@ use regex::Regex;
 use url::Url;
 
 use ardite::{Definition, Type, DriverConfig, Schema};
+use ardite::schema::StringFormat;
 
 fn create_basic_definition() -> Definition {
   // TODO: use order in file, not serde’s `BTreeMap` order.
@@ -21,7 +22,7 @@ fn create_basic_definition() -> Definition {
       let mut email = Schema::string();
       email.set_min_length(4);
       email.set_max_length(256);
-      email.set_pattern(Regex::new(r".+@.+\..+").unwrap());
+      email.set_format(StringFormat::Email);
       email
     });
     person.add_property("name", {
@@ -50,9 +51,12 @@ fn create_basic_definition() -> Definition {
     post.add_property("topic", {
       Schema::enum_(vec!["showcase", "help", "ama"])
     });
+    post.add_property("author", Schema::reference("person"));
     post
   });
 
+  definition.resolve_references().unwrap();
+
   definition
 }
 
@@ -80,17 +84,24 @@ fn create_kitchen_sink_definition() -> Definition {
         sub_array.set_items(Schema::null());
         sub_array
       });
+      array.set_min_items(1);
+      array.set_max_items(4);
       array
     });
     c.add_property("boolean", Schema::boolean());
     c.add_property("enum", Schema::enum_(vec![value!("red"), value!(2), value!(false), value!({ "hello" => { "world" => 8 } })]));
     c.add_property("integer", {
-      let mut number = Schema::number();
-      number.set_multiple_of(1.0);
+      let mut number = Schema::integer();
       number.set_minimum(8.0);
       number.set_maximum(30.0);
       number
     });
+    c.add_property("map", {
+      let mut map = Schema::object();
+      map.add_pattern_property(Regex::new("^[a-z]{2}$").unwrap(), Schema::string());
+      map.set_additional_properties(Schema::number());
+      map
+    });
     c.add_property("null", Schema::null());
     c.add_property("number", {
       let mut number = Schema::number();
@@ -134,6 +145,15 @@ fn test_basic_yaml() {
   );
 }
 
+#[test]
+fn test_basic_references() {
+  let definition = Definition::from_file(PathBuf::from("tests/fixtures/definitions/basic.yml")).unwrap();
+  let post = definition.get_type(&"post".to_owned()).unwrap();
+  assert!(post.schema().get(vec!["author".to_owned(), "email".to_owned()]).is_some());
+  assert!(post.schema().validate_value(&value!({ "headline" => "Hello", "author" => { "email" => "a@b.c" } })).is_ok());
+  assert!(post.schema().validate_value(&value!({ "headline" => "Hello", "author" => { "name" => "Ada" } })).is_err());
+}
+
 #[test]
 fn test_kitchen_sink_yaml() {
   assert_eq!(
@@ -141,3 +161,8 @@ fn test_kitchen_sink_yaml() {
     create_kitchen_sink_definition()
   );
 }
+
+#[test]
+fn test_unknown_keyword_yaml() {
+  assert!(Definition::from_file(PathBuf::from("tests/fixtures/definitions/unknown-keyword.yml")).is_err());
+}